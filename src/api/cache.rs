@@ -6,6 +6,8 @@ use std::fs;
 use std::path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::error::{HvError, Result};
+
 const CACHE_VERSION: &str = "v1";
 const CACHE_EXPIRE_DAYS: u64 = 32;
 
@@ -22,31 +24,37 @@ pub struct Cache {
 }
 
 impl Cache {
-    pub fn new(file_path: path::PathBuf, use_cache: bool) -> Self {
+    pub fn new(file_path: path::PathBuf, use_cache: bool) -> Result<Self> {
         if !use_cache {
-            return Cache {
+            return Ok(Cache {
                 file_path: path::PathBuf::new(),
                 use_cache: false,
-            };
+            });
         }
-        let dir = file_path.parent().expect(&format!(
-            "failed to get parent dir from '{}'",
-            file_path.display()
-        ));
-        fs::create_dir_all(dir).expect(&format!("failed to create cache dir '{}'", dir.display()));
+        let dir = file_path.parent().ok_or_else(|| HvError::CacheIo {
+            path: file_path.clone(),
+            source: std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "failed to get parent dir",
+            ),
+        })?;
+        fs::create_dir_all(dir).map_err(|source| HvError::CacheIo {
+            path: dir.to_path_buf(),
+            source,
+        })?;
         if !file_path.exists() {
-            fs::File::create(&file_path).expect(&format!(
-                "failed to create cache file '{}'",
-                file_path.display()
-            ));
+            fs::File::create(&file_path).map_err(|source| HvError::CacheIo {
+                path: file_path.clone(),
+                source,
+            })?;
         }
-        Cache {
+        Ok(Cache {
             file_path,
             use_cache: true,
-        }
+        })
     }
 
-    fn read_cache_content(&self) -> Result<HashMap<String, CacheEntity>, anyhow::Error> {
+    fn read_cache_content(&self) -> anyhow::Result<HashMap<String, CacheEntity>> {
         let content = fs::read_to_string(&self.file_path)?;
         if content.is_empty() {
             return Ok(HashMap::new());
@@ -135,7 +143,7 @@ mod tests {
             fs::remove_dir_all(&dir).unwrap();
         }
         let file_path = dir.join("cache.json");
-        Cache::new(file_path.clone(), use_cache)
+        Cache::new(file_path.clone(), use_cache).unwrap()
     }
 
     pub fn get_expected_expire() -> u64 {
@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path;
+
+use serde::Deserialize;
+
+use super::geocode::Address;
+use crate::error::{HvError, Result};
+
+// only this many entries sharing the rarest words with the query are scored
+const MAX_SCORED_ENTRIES: usize = 100;
+// words at least this similar are taken as the same word with a typo
+const MIN_WORD_SIMILARITY: f64 = 0.75;
+
+const STREET_TYPES: [(&str, &str); 24] = [
+    ("st", "street"),
+    ("rd", "road"),
+    ("ave", "avenue"),
+    ("av", "avenue"),
+    ("dr", "drive"),
+    ("pl", "place"),
+    ("cres", "crescent"),
+    ("cr", "crescent"),
+    ("ct", "court"),
+    ("hwy", "highway"),
+    ("pde", "parade"),
+    ("cl", "close"),
+    ("ln", "lane"),
+    ("tce", "terrace"),
+    ("blvd", "boulevard"),
+    ("cct", "circuit"),
+    ("gr", "grove"),
+    ("sq", "square"),
+    ("esp", "esplanade"),
+    ("pkwy", "parkway"),
+    ("wy", "way"),
+    ("bvd", "boulevard"),
+    ("gdns", "gardens"),
+    ("cir", "circle"),
+];
+
+const UNIT_PREFIXES: [&str; 8] = [
+    "unit",
+    "u",
+    "apartment",
+    "apt",
+    "flat",
+    "shop",
+    "suite",
+    "villa",
+];
+
+fn is_street_type(word: &str) -> bool {
+    STREET_TYPES
+        .iter()
+        .any(|(a, full)| *a == word || *full == word)
+}
+
+fn is_number(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit())
+}
+
+// Words of the address, lower case and without punctuation, with
+// - street types spelled out, "St" -> "street", unless it's more likely "Saint" as in "St Ives".
+// - units written as "5/12", from "Unit 5, 12", "U5 12" or "5 / 12".
+// - the state as "nsw" and the country dropped.
+pub fn normalise(address: &str) -> Vec<String> {
+    let mut address = format!(" {} ", address.to_lowercase())
+        .replace([',', '.'], " ")
+        .replace(" new south wales ", " nsw ")
+        .replace(" australia ", " ");
+    while address.contains(" /") || address.contains("/ ") {
+        address = address.replace(" /", "/").replace("/ ", "/");
+    }
+    let raw = address.split_whitespace().collect::<Vec<&str>>();
+    let mut words = Vec::<String>::new();
+    let mut idx = 0;
+    while idx < raw.len() {
+        let word = raw[idx];
+        // the unit number is either in the word as "u5", or after it as "unit 5"
+        let (unit, after) = match word.strip_prefix('u') {
+            Some(unit) if is_number(unit) => (Some(unit), idx + 1),
+            _ if UNIT_PREFIXES.contains(&word) => match raw.get(idx + 1) {
+                Some(unit) if is_number(unit) => (Some(*unit), idx + 2),
+                _ => (None, idx + 1),
+            },
+            _ => (None, idx + 1),
+        };
+        if let Some(unit) = unit {
+            match raw.get(after) {
+                Some(number) if !unit.contains('/') && is_number(number) => {
+                    words.push(format!("{}/{}", unit, number));
+                    idx = after + 1;
+                }
+                _ => {
+                    words.push(unit.to_owned());
+                    idx = after;
+                }
+            }
+            continue;
+        }
+        let previous = words.last().map(|w| w.as_str());
+        let after_street_name = previous
+            .is_some_and(|p| !is_number(p) && !is_street_type(p) && !UNIT_PREFIXES.contains(&p));
+        match STREET_TYPES.iter().find(|(a, _)| *a == word) {
+            Some((_, full)) if after_street_name => words.push((*full).to_owned()),
+            _ => words.push(word.to_owned()),
+        }
+        idx += 1;
+    }
+    words
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// numbers only match exactly, a typo in a house number is a different house
+fn word_similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    if is_number(a) || is_number(b) {
+        return 0.0;
+    }
+    let longest = a.chars().count().max(b.chars().count());
+    let similarity = 1.0 - levenshtein(a, b) as f64 / longest as f64;
+    if similarity < MIN_WORD_SIMILARITY {
+        0.0
+    } else {
+        similarity
+    }
+}
+
+// Mostly how much of the query is found in the entry, and a bit of how much of the entry is in
+// the query, so that the state or postcode missing from the query costs little. Each number
+// of the query that conflicts with a number of the entry, e.g. a different house number,
+// halves the confidence.
+fn confidence(query: &[String], entry: &[String]) -> f64 {
+    if query.is_empty() || entry.is_empty() {
+        return 0.0;
+    }
+    let mut entry_matched = vec![false; entry.len()];
+    let mut matched = 0.0;
+    let mut unmatched_numbers = 0;
+    for word in query {
+        let best = entry
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !entry_matched[*idx])
+            .map(|(idx, w)| (idx, word_similarity(word, w)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((idx, similarity)) if similarity > 0.0 => {
+                entry_matched[idx] = true;
+                matched += similarity;
+            }
+            _ if is_number(word) => unmatched_numbers += 1,
+            _ => {}
+        }
+    }
+    let conflicts = unmatched_numbers.min(
+        entry
+            .iter()
+            .zip(entry_matched.iter())
+            .filter(|(w, m)| !**m && is_number(w))
+            .count(),
+    );
+    let recall = matched / query.len() as f64;
+    let precision = entry_matched.iter().filter(|m| **m).count() as f64 / entry.len() as f64;
+    (0.8 * recall + 0.2 * precision) * 0.5_f64.powi(conflicts as i32)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GazetteerMatch {
+    pub address: Address,
+    // from 0 to 1, 1 when the address is the same after normalisation
+    pub confidence: f64,
+}
+
+#[derive(Deserialize)]
+struct GazetteerRecord {
+    address: String,
+    lat: f64,
+    lon: f64,
+}
+
+// Addresses from a csv with `address`, `lat` and `lon` columns, e.g. an extract of G-NAF.
+pub struct Gazetteer {
+    addresses: Vec<(Address, Vec<String>)>,
+    // word -> index of every address with it
+    index: HashMap<String, Vec<usize>>,
+}
+
+impl Gazetteer {
+    pub fn load(file_path: &path::Path) -> Result<Self> {
+        let file = fs::File::open(file_path).map_err(|e| HvError::Gazetteer {
+            path: file_path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        Gazetteer::from_reader(file).map_err(|reason| HvError::Gazetteer {
+            path: file_path.to_path_buf(),
+            reason,
+        })
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> std::result::Result<Self, String> {
+        let mut addresses = Vec::new();
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        for (idx, record) in reader.deserialize::<GazetteerRecord>().enumerate() {
+            // rows are numbered from 1, not counting the header
+            let record = record.map_err(|e| format!("row {}: {}", idx + 1, e))?;
+            addresses.push(Address {
+                full_address: record.address,
+                latitude_degree: record.lat,
+                longitude_degree: record.lon,
+            });
+        }
+        Ok(Gazetteer::new(addresses))
+    }
+
+    pub fn new(addresses: Vec<Address>) -> Self {
+        let addresses = addresses
+            .into_iter()
+            .map(|a| {
+                let words = normalise(&a.full_address);
+                (a, words)
+            })
+            .collect::<Vec<(Address, Vec<String>)>>();
+        let mut index = HashMap::<String, Vec<usize>>::new();
+        for (idx, (_, words)) in addresses.iter().enumerate() {
+            for word in words {
+                let entries = index.entry(word.to_owned()).or_default();
+                if entries.last() != Some(&idx) {
+                    entries.push(idx);
+                }
+            }
+        }
+        Gazetteer { addresses, index }
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    // Every word shared with the query counts by how rare it is, so that "street", "nsw" or a
+    // house number found in most entries count for little. A query word not in any entry is
+    // likely a typo, the words similar to it count instead.
+    fn shortlist(&self, query: &[String]) -> Vec<(usize, f64)> {
+        let total = self.addresses.len() as f64;
+        let mut scores = HashMap::<usize, f64>::new();
+        for word in query.iter() {
+            let similar = match self.index.get_key_value(word) {
+                Some((word, _)) => vec![(word, 1.0)],
+                None => self
+                    .index
+                    .keys()
+                    .map(|w| (w, word_similarity(word, w)))
+                    .filter(|(_, similarity)| *similarity > 0.0)
+                    .collect(),
+            };
+            for (word, similarity) in similar {
+                let entries = &self.index[word];
+                let weight = similarity * (1.0 + total / entries.len() as f64).ln();
+                for idx in entries {
+                    *scores.entry(*idx).or_default() += weight;
+                }
+            }
+        }
+        let mut shortlist = scores.into_iter().collect::<Vec<(usize, f64)>>();
+        shortlist.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        shortlist.truncate(MAX_SCORED_ENTRIES);
+        shortlist
+    }
+
+    // Addresses at least `min_confidence` alike the query, the most confident first. Only the
+    // addresses sharing the rarest words with the query are compared.
+    pub fn search(&self, address: &str, min_confidence: f64) -> Vec<GazetteerMatch> {
+        let query = normalise(address);
+        let mut found = self
+            .shortlist(&query)
+            .into_iter()
+            .map(|(idx, _)| {
+                let (address, words) = &self.addresses[idx];
+                GazetteerMatch {
+                    address: address.clone(),
+                    confidence: confidence(&query, words),
+                }
+            })
+            .filter(|m| m.confidence >= min_confidence)
+            .collect::<Vec<GazetteerMatch>>();
+        found.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        found
+    }
+
+    pub fn find(&self, address: &str) -> Option<GazetteerMatch> {
+        self.search(address, 0.0).into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn words(address: &str) -> String {
+        normalise(address).join(" ")
+    }
+
+    fn gazetteer() -> Gazetteer {
+        Gazetteer::from_reader(
+            "address,lat,lon
+\"56 Iris Street, Frenchs Forest NSW 2086\",-33.7565,151.2286
+\"58 Iris Street, Frenchs Forest NSW 2086\",-33.7566,151.2287
+\"5/12 Mona Vale Road, St Ives NSW 2075\",-33.73,151.16
+\"56 Iris Street, Beacon Hill NSW 2100\",-33.75,151.25
+"
+            .as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn street_types_spelled_out() {
+        assert_eq!(
+            words("56 Iris St., Frenchs Forest, New South Wales, Australia"),
+            "56 iris street frenchs forest nsw"
+        );
+        assert_eq!(words("1 Mona Vale Rd"), "1 mona vale road");
+        assert_eq!(words("3 Pacific Hwy"), "3 pacific highway");
+    }
+
+    #[test]
+    fn saint_kept() {
+        assert_eq!(words("12 St Johns Rd"), "12 st johns road");
+        assert_eq!(words("1 Mona Vale Rd St Ives"), "1 mona vale road st ives");
+    }
+
+    #[test]
+    fn units() {
+        for address in [
+            "5/12 Mona Vale Road",
+            "5 / 12 Mona Vale Road",
+            "Unit 5, 12 Mona Vale Road",
+            "unit 5/12 Mona Vale Road",
+            "U5 12 Mona Vale Road",
+            "Apt 5 12 Mona Vale Road",
+        ] {
+            assert_eq!(words(address), "5/12 mona vale road", "{}", address);
+        }
+        assert_eq!(words("Unit 5"), "5");
+        assert_eq!(words("Shop Street"), "shop street");
+    }
+
+    #[test]
+    fn exact_match_fully_confident() {
+        let found = gazetteer()
+            .find("56 Iris St Frenchs Forest NSW 2086")
+            .unwrap();
+        assert_eq!(
+            found.address.full_address,
+            "56 Iris Street, Frenchs Forest NSW 2086"
+        );
+        assert_eq!(found.confidence, 1.0);
+    }
+
+    #[test]
+    fn typo_matched() {
+        let found = gazetteer().find("56 Iris Stret, Frenchs Forrest").unwrap();
+        assert_eq!(
+            found.address.full_address,
+            "56 Iris Street, Frenchs Forest NSW 2086"
+        );
+        assert!(found.confidence > 0.8 && found.confidence < 1.0);
+    }
+
+    #[test]
+    fn unit_matched() {
+        let found = gazetteer()
+            .find("Unit 5, 12 Mona Vale Rd, St Ives")
+            .unwrap();
+        assert_eq!(found.address.latitude_degree, -33.73);
+        assert!(found.confidence > 0.9);
+    }
+
+    #[test]
+    fn different_house_number_not_confident() {
+        let found = gazetteer().search("60 Iris Street Frenchs Forest", 0.0);
+        assert!(found.iter().all(|m| m.confidence < 0.5));
+    }
+
+    #[test]
+    fn suburb_picks_the_street() {
+        let found = gazetteer().search("56 Iris Street Beacon Hill", 0.7);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].address.full_address,
+            "56 Iris Street, Beacon Hill NSW 2100"
+        );
+    }
+
+    #[test]
+    fn ambiguous_both_found() {
+        let found = gazetteer().search("56 Iris Street", 0.7);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].confidence, found[1].confidence);
+    }
+
+    #[test]
+    fn typo_found_among_common_words() {
+        // every street of the suburb has a 12, they all share 5 words with the query
+        let mut addresses = (0..300)
+            .map(|i| format!("12 Zq{:03} Street, Kellyville NSW 2155", i))
+            .chain((0..2000).map(|i| format!("12 Zq{:04} Street, Rouse Hill NSW 2155", i)))
+            .map(|full_address| Address {
+                full_address,
+                latitude_degree: -33.7,
+                longitude_degree: 150.9,
+            })
+            .collect::<Vec<Address>>();
+        addresses.push(Address {
+            full_address: "12 Wattle Street, Kellyville NSW 2155".to_owned(),
+            latitude_degree: -33.71,
+            longitude_degree: 150.95,
+        });
+        let gazetteer = Gazetteer::new(addresses);
+        let found = gazetteer
+            .find("12 Watle Street, Kellyville NSW 2155")
+            .unwrap();
+        assert_eq!(
+            found.address.full_address,
+            "12 Wattle Street, Kellyville NSW 2155"
+        );
+        // the suburb is rarer than the rest
+        let found = gazetteer.search("12 Street Kellyville", 0.0);
+        assert!(found
+            .iter()
+            .all(|m| m.address.full_address.contains("Kellyville")));
+    }
+
+    #[test]
+    fn malformed_row() {
+        let error =
+            Gazetteer::from_reader("address,lat,lon\na,-33.0,151.0\nb,abc,151.0\n".as_bytes())
+                .err()
+                .unwrap();
+        assert!(error.starts_with("row 2"));
+    }
+}
@@ -1,5 +1,16 @@
+use std::collections::HashMap;
+use std::path;
+
 use serde::Deserialize;
 
+use super::gazetteer::Gazetteer;
+use crate::error::{HvError, Result};
+
+pub const MAPS_CO_URL: &str = "https://geocode.maps.co/search";
+pub const NOMINATIM_URL: &str = "https://nominatim.openstreetmap.org/search";
+// below this, an address in the gazetteer is not taken as the one looked up
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.7;
+
 #[derive(Deserialize, Debug)]
 struct GeoCodeResponse {
     lat: String,
@@ -7,48 +18,283 @@ struct GeoCodeResponse {
     display_name: String,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Address {
     pub full_address: String,
     pub latitude_degree: f64,
     pub longitude_degree: f64,
 }
 
-pub fn find_address(address: &str) -> Address {
-    let url = format!(
-        "https://geocode.maps.co/search?q={}",
-        address.replace(" ", "+")
-    );
+pub trait Geocoder {
+    // every candidate found for the address, the most relevant first
+    fn search(&self, address: &str) -> Result<Vec<Address>>;
+
+    fn find_address(&self, address: &str) -> Result<Address> {
+        let mut candidates = self.search(address)?;
+        if candidates.len() > 1 {
+            warn!(
+                "more than one results found for address '{}', results are: '{:#?}'.",
+                address, candidates
+            );
+            warn!("The first address will be used, if it's not expected, please specify more specific address");
+        } else if candidates.is_empty() {
+            return Err(HvError::GeocodeNotFound(address.to_owned()));
+        }
+        let found = candidates.swap_remove(0);
+        trace!(
+            "address found as '{}' at {}, {}",
+            found.full_address,
+            found.latitude_degree,
+            found.longitude_degree
+        );
+        Ok(found)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum GeocoderKind {
+    MapsCo,
+    Nominatim,
+    Gazetteer,
+}
+
+// Which geocoder to use and how to set it up.
+#[derive(Clone, Debug)]
+pub enum GeocoderConfig {
+    MapsCo {
+        api_key: Option<String>,
+    },
+    Nominatim {
+        url: String,
+        email: Option<String>,
+    },
+    // a csv file of address, lat and lon
+    Gazetteer {
+        file_path: path::PathBuf,
+        min_confidence: f64,
+    },
+}
+
+impl Default for GeocoderConfig {
+    fn default() -> Self {
+        GeocoderConfig::MapsCo { api_key: None }
+    }
+}
+
+impl GeocoderConfig {
+    pub fn build(&self) -> Result<Box<dyn Geocoder>> {
+        Ok(match self {
+            GeocoderConfig::MapsCo { api_key } => Box::new(MapsCoGeocoder {
+                api_key: api_key.clone(),
+            }),
+            GeocoderConfig::Nominatim { url, email } => Box::new(NominatimGeocoder {
+                url: url.to_owned(),
+                email: email.clone(),
+            }),
+            GeocoderConfig::Gazetteer {
+                file_path,
+                min_confidence,
+            } => Box::new(CsvGeocoder {
+                gazetteer: Gazetteer::load(file_path)?,
+                min_confidence: *min_confidence,
+            }),
+        })
+    }
+}
+
+fn parse_position(endpoint: &str, name: &str, value: &str) -> Result<f64> {
+    value
+        .parse::<f64>()
+        .map_err(|_| HvError::MalformedResponse {
+            endpoint: endpoint.to_owned(),
+            reason: format!(
+                "failed to parse {} from the response to float, got '{}'",
+                name, value
+            ),
+        })
+}
+
+// maps.co is a proxy of nominatim, so both respond in the same format.
+fn search_nominatim(
+    endpoint: &str,
+    params: &[(&str, &str)],
+    address: &str,
+) -> Result<Vec<Address>> {
+    let mut params = params.to_vec();
+    params.push(("q", address));
+    let url = reqwest::Url::parse_with_params(endpoint, &params).map_err(|e| {
+        HvError::MalformedResponse {
+            endpoint: endpoint.to_owned(),
+            reason: format!("invalid url: {}", e),
+        }
+    })?;
     trace!("fetch '{}' to find geo location", url);
-    let resp = reqwest::blocking::get(url)
-        .unwrap()
-        .json::<Vec<GeoCodeResponse>>()
+    let resp = reqwest::blocking::Client::builder()
+        .user_agent(concat!("hv-distance/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .and_then(|c| c.get(url.clone()).send())
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.json::<Vec<GeoCodeResponse>>())
+        .map_err(|source| HvError::UpstreamHttp {
+            url: endpoint.to_owned(),
+            source,
+        })?;
+    resp.into_iter()
+        .map(|r| {
+            Ok(Address {
+                latitude_degree: parse_position(endpoint, "latitude", &r.lat)?,
+                longitude_degree: parse_position(endpoint, "longitude", &r.lon)?,
+                full_address: r.display_name,
+            })
+        })
+        .collect()
+}
+
+pub struct MapsCoGeocoder {
+    pub api_key: Option<String>,
+}
+
+impl Geocoder for MapsCoGeocoder {
+    fn search(&self, address: &str) -> Result<Vec<Address>> {
+        let params = match &self.api_key {
+            Some(api_key) => vec![("api_key", api_key.as_str())],
+            None => vec![],
+        };
+        search_nominatim(MAPS_CO_URL, &params, address)
+    }
+}
+
+// The public instance asks for an email, or at least an identifying user agent, with every request.
+pub struct NominatimGeocoder {
+    pub url: String,
+    pub email: Option<String>,
+}
+
+impl Geocoder for NominatimGeocoder {
+    fn search(&self, address: &str) -> Result<Vec<Address>> {
+        let mut params = vec![("format", "json"), ("countrycodes", "au")];
+        if let Some(email) = &self.email {
+            params.push(("email", email));
+        }
+        search_nominatim(&self.url, &params, address)
+    }
+}
+
+// Looks up addresses in a gazetteer, so that no request is made. Only addresses at least
+// `min_confidence` alike are found.
+pub struct CsvGeocoder {
+    pub gazetteer: Gazetteer,
+    pub min_confidence: f64,
+}
+
+impl Geocoder for CsvGeocoder {
+    fn search(&self, address: &str) -> Result<Vec<Address>> {
+        let found = self.gazetteer.search(address, self.min_confidence);
+        for m in found.iter() {
+            debug!(
+                "'{}' found in gazetteer with confidence {:.2}",
+                m.address.full_address, m.confidence
+            );
+        }
+        Ok(found.into_iter().map(|m| m.address).collect())
+    }
+}
+
+// Answers with exactly what it's given, e.g. for tests.
+#[derive(Default)]
+pub struct InMemoryGeocoder {
+    addresses: HashMap<String, Vec<Address>>,
+}
+
+impl InMemoryGeocoder {
+    pub fn new() -> Self {
+        InMemoryGeocoder::default()
+    }
+
+    pub fn with(mut self, query: &str, address: Address) -> Self {
+        self.addresses
+            .entry(query.to_owned())
+            .or_default()
+            .push(address);
+        self
+    }
+}
+
+impl Geocoder for InMemoryGeocoder {
+    fn search(&self, address: &str) -> Result<Vec<Address>> {
+        Ok(self.addresses.get(address).cloned().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn address(full_address: &str) -> Address {
+        Address {
+            full_address: full_address.to_owned(),
+            latitude_degree: -33.7565,
+            longitude_degree: 151.2286,
+        }
+    }
+
+    #[test]
+    fn in_memory_first_candidate() {
+        let geocoder = InMemoryGeocoder::new()
+            .with("Iris St", address("56 Iris Street Frenchs Forest"))
+            .with("Iris St", address("56 Iris Street Beacon Hill"));
+        assert_eq!(geocoder.search("Iris St").unwrap().len(), 2);
+        assert_eq!(
+            geocoder.find_address("Iris St").unwrap(),
+            address("56 Iris Street Frenchs Forest")
+        );
+    }
+
+    #[test]
+    fn not_found() {
+        let geocoder = InMemoryGeocoder::new();
+        assert!(matches!(
+            geocoder.find_address("nowhere"),
+            Err(HvError::GeocodeNotFound(a)) if a == "nowhere"
+        ));
+    }
+
+    #[test]
+    fn gazetteer_fuzzy() {
+        let geocoder = CsvGeocoder {
+            gazetteer: Gazetteer::new(vec![address("56 Iris Street, Frenchs Forest NSW 2086")]),
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+        };
+        let found = geocoder
+            .find_address("56  iris st frenchs forest, NSW 2086.")
+            .unwrap();
+        assert_eq!(found, address("56 Iris Street, Frenchs Forest NSW 2086"));
+        assert!(geocoder.search("12 Smith Road").unwrap().is_empty());
+    }
+
+    #[test]
+    fn config_builds_gazetteer() {
+        let file_path =
+            std::env::temp_dir().join(format!("hv-distance-gazetteer-{}.csv", std::process::id()));
+        std::fs::write(&file_path, "address,lat,lon\nsomewhere,-33.0,151.0\n").unwrap();
+        let geocoder = GeocoderConfig::Gazetteer {
+            file_path: file_path.clone(),
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+        }
+        .build()
         .unwrap();
-    if resp.len() > 1 {
-        warn!(
-            "more than one results found for address '{}', results are: '{:#?}'.",
-            address, resp
+        assert_eq!(
+            geocoder.find_address("Somewhere").unwrap().latitude_degree,
+            -33.0
         );
-        warn!("The first address will be used, if it's not expected, please specify more specific address");
-    } else if resp.is_empty() {
-        panic!("no result found for address '{}'", address);
-    }
-    let latitude = resp[0].lat.parse::<f64>().expect(&format!(
-        "failed to parse latitude from the response to float, got '{}'",
-        resp[0].lat
-    ));
-    let longitude = resp[0].lon.parse::<f64>().expect(&format!(
-        "failed to parse longitude from the response to float, got '{}'",
-        resp[0].lon
-    ));
-    trace!(
-        "address found as '{}' at {}, {}",
-        resp[0].display_name,
-        latitude,
-        longitude
-    );
-    Address {
-        full_address: resp[0].display_name.to_owned(),
-        latitude_degree: latitude,
-        longitude_degree: longitude,
+        std::fs::remove_file(&file_path).unwrap();
+        assert!(matches!(
+            GeocoderConfig::Gazetteer {
+                file_path,
+                min_confidence: DEFAULT_MIN_CONFIDENCE
+            }
+            .build(),
+            Err(HvError::Gazetteer { .. })
+        ));
     }
 }
@@ -1,3 +1,4 @@
 pub mod cache;
+pub mod gazetteer;
 pub mod geocode;
 pub mod property_data_map;
@@ -2,6 +2,7 @@ use serde::Deserialize;
 use std::collections::HashMap;
 
 use crate::api::cache::Caching;
+use crate::error::{HvError, Result};
 
 use super::cache::Cache;
 
@@ -18,28 +19,42 @@ fn get_form_data(suburb_code: &str) -> HashMap<&str, &str> {
     ])
 }
 
-pub fn server_init_init(cache_store: &Cache) -> HashMap<String, [String; 4]> {
+fn post_form(endpoint: &str, suburb_code: &str) -> Result<String> {
+    let client = reqwest::blocking::Client::new();
+    client
+        .post(endpoint)
+        .form(&get_form_data(suburb_code))
+        .send()
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.text())
+        .map_err(|source| HvError::UpstreamHttp {
+            url: endpoint.to_owned(),
+            source,
+        })
+}
+
+fn malformed_response(endpoint: &str, e: serde_json::Error) -> HvError {
+    HvError::MalformedResponse {
+        endpoint: endpoint.to_owned(),
+        reason: e.to_string(),
+    }
+}
+
+pub fn server_init_init(cache_store: &Cache) -> Result<HashMap<String, [String; 4]>> {
     #[derive(Deserialize, Debug)]
     struct RawInitResponse {
         #[serde(rename(deserialize = "Array_Suburb"))]
         array_suburb: String,
     }
     let cache_key = "property_data_map_server_init_init";
+    let endpoint =
+        "https://www.propertydatamap.com.au/Property/00_PHP_9/Server_Initial_Initial.php";
 
     let body_text = match cache_store.read(cache_key) {
         Ok(val) => val,
         Err(e) => {
             debug!("cache not found for '{}'.\nError: {}", cache_key, e);
-            let client = reqwest::blocking::Client::new();
-            let endpoint =
-                "https://www.propertydatamap.com.au/Property/00_PHP_9/Server_Initial_Initial.php";
-            let response = client
-                .post(endpoint)
-                .form(&get_form_data("4167"))
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
+            let response = post_form(endpoint, "4167")?;
             let write_result = cache_store.write(cache_key, response.clone());
             if write_result.is_err() {
                 warn!(
@@ -52,12 +67,13 @@ pub fn server_init_init(cache_store: &Cache) -> HashMap<String, [String; 4]> {
         }
     };
 
-    let body_json = serde_json::from_str::<RawInitResponse>(&body_text).unwrap();
+    let body_json = serde_json::from_str::<RawInitResponse>(&body_text)
+        .map_err(|e| malformed_response(endpoint, e))?;
     let raw_suburb_map =
         serde_json::from_str::<HashMap<String, [String; 4]>>(&body_json.array_suburb)
-            .expect("failed to parse ARRAY_SUBURB to map[str -> [str; 4]]");
+            .map_err(|e| malformed_response(endpoint, e))?;
     trace!("suburb goe location fetched");
-    raw_suburb_map
+    Ok(raw_suburb_map)
 }
 
 #[derive(Deserialize, Debug)]
@@ -75,13 +91,36 @@ pub struct SelectedLatLon {
 pub struct SelectSuburbResponse {
     pub selected_lat_lon: HashMap<String, SelectedLatLon>,
     pub selected_popup_info: HashMap<String, Vec<String>>,
+    // not every suburb comes with its boundary
+    pub boundary: Option<SuburbPolygon>,
+}
+
+// The boundary is a geojson string like the lines, it's looked up on its own so that it's
+// still found when there is no line in the suburb. Without it, the suburb is ranked by its
+// centroid, which is logged since no sample response has shown the key yet.
+fn parse_boundary(array_data: &str, suburb_name: &str) -> Option<SuburbPolygon> {
+    let array_data = serde_json::from_str::<serde_json::Value>(array_data).ok()?;
+    let Some(raw) = array_data.get("Geometry_Suburb").and_then(|v| v.as_str()) else {
+        debug!(
+            "no Geometry_Suburb in the response of {}, its centroid is used instead",
+            suburb_name
+        );
+        return None;
+    };
+    match serde_json::from_str::<SuburbPolygon>(raw) {
+        Ok(boundary) => Some(boundary),
+        Err(e) => {
+            warn!("failed to parse the boundary of {}: {}", suburb_name, e);
+            None
+        }
+    }
 }
 
 pub fn select_suburb(
     suburb_id: u16,
     suburb_name: &str,
     cache_store: &Cache,
-) -> SelectSuburbResponse {
+) -> Result<SelectSuburbResponse> {
     #[derive(Deserialize, Debug)]
     struct RawSelectSuburbResponse {
         #[serde(rename(deserialize = "Array_Data"))]
@@ -98,21 +137,14 @@ pub fn select_suburb(
     }
 
     let cache_key = format!("property_data_map_select_suburb_{}", suburb_id);
+    let endpoint =
+        "https://www.propertydatamap.com.au/Property/00_PHP_9/Server_Map_SelectSuburb.php";
     let body_text = match cache_store.read(&cache_key) {
         Ok(val) => val,
         Err(e) => {
             debug!("cache not found for '{}'.\nError: {}", cache_key, e);
             debug!("fetching suburb response parsed for {}", suburb_name);
-            let client = reqwest::blocking::Client::new();
-            let endpoint =
-                "https://www.propertydatamap.com.au/Property/00_PHP_9/Server_Map_SelectSuburb.php";
-            let response = client
-                .post(endpoint)
-                .form(&get_form_data(&suburb_id.to_string()))
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
+            let response = post_form(endpoint, &suburb_id.to_string())?;
             let write_result = cache_store.write(&cache_key, response.clone());
             if write_result.is_err() {
                 warn!(
@@ -124,7 +156,9 @@ pub fn select_suburb(
             response
         }
     };
-    let body_json = serde_json::from_str::<RawSelectSuburbResponse>(&body_text).unwrap();
+    let body_json = serde_json::from_str::<RawSelectSuburbResponse>(&body_text)
+        .map_err(|e| malformed_response(endpoint, e))?;
+    let boundary = parse_boundary(&body_json.array_data, suburb_name);
     if body_json
         .array_data
         // when there is no voltage lines, the return value becomes an array of some random value
@@ -132,21 +166,44 @@ pub fn select_suburb(
         .contains("Geometry_Selected_Popup_Info\":[[\"")
     {
         debug!("there is no high voltage power line in {}", suburb_name);
-        return SelectSuburbResponse {
+        return Ok(SelectSuburbResponse {
             selected_lat_lon: HashMap::new(),
             selected_popup_info: HashMap::new(),
-        };
+            boundary,
+        });
     }
     let array_data = serde_json::from_str::<RawArrayData>(&body_json.array_data)
-        .expect("failed to parse array_data for suburb_id={}");
+        .map_err(|e| malformed_response(endpoint, e))?;
     let mut selected_lat_lon = HashMap::<String, SelectedLatLon>::new();
     for (k, v) in array_data.geometry_selected_latlon {
         let val = serde_json::from_str::<SelectedLatLon>(&v)
-            .expect("failed to parse Geometry_Selected_LatLon");
+            .map_err(|e| malformed_response(endpoint, e))?;
         selected_lat_lon.insert(k, val);
     }
-    SelectSuburbResponse {
+    Ok(SelectSuburbResponse {
         selected_lat_lon,
         selected_popup_info: array_data.geometry_selected_popup_info,
+        boundary,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn boundary_found() {
+        let array_data = r#"{"Geometry_Suburb": "{\"type\":\"Polygon\",\"coordinates\":[[[151.2,-33.7],[151.3,-33.7],[151.3,-33.8],[151.2,-33.7]]]}", "Geometry_Selected_Popup_Info": [["x"]]}"#;
+        let boundary = parse_boundary(array_data, "FRENCHS FOREST").unwrap();
+        assert_eq!(boundary.r#type, "Polygon");
+        assert_eq!(boundary.coordinates[0].len(), 4);
+        assert_eq!(boundary.coordinates[0][1], [151.3, -33.7]);
+    }
+
+    #[test]
+    fn boundary_missing_or_malformed() {
+        assert!(parse_boundary(r#"{"Geometry_Selected_LatLon": {}}"#, "A").is_none());
+        assert!(parse_boundary(r#"{"Geometry_Suburb": "not a polygon"}"#, "A").is_none());
+        assert!(parse_boundary("not a json", "A").is_none());
     }
 }
@@ -0,0 +1,412 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{HvError, Result};
+use crate::query::{DistanceQuery, DistanceReport, QueryLocation, QuerySession};
+
+#[derive(Debug, PartialEq)]
+pub enum BatchFormat {
+    Csv,
+    Jsonl,
+}
+
+impl BatchFormat {
+    pub fn from_path(file_path: &path::Path) -> Result<Self> {
+        match file_path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Ok(BatchFormat::Csv),
+            Some("jsonl") => Ok(BatchFormat::Jsonl),
+            _ => Err(HvError::UnsupportedBatchFormat(file_path.to_path_buf())),
+        }
+    }
+}
+
+// A row can either have an address, or a pair of lat and lon.
+#[derive(Deserialize, Debug)]
+struct RawBatchRecord {
+    id: Option<String>,
+    address: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+}
+
+pub struct BatchRow {
+    pub id: String,
+    // rows that can not be parsed are kept, so that they show up in the output with the error.
+    pub location: Result<QueryLocation>,
+}
+
+pub struct BatchResult {
+    pub id: String,
+    pub input: String,
+    pub report: Result<DistanceReport>,
+}
+
+fn to_batch_row(row: usize, record: RawBatchRecord) -> BatchRow {
+    let id = record.id.unwrap_or_else(|| row.to_string());
+    let location = match (record.address, record.lat, record.lon) {
+        (Some(address), _, _) if !address.trim().is_empty() => Ok(QueryLocation::Address(address)),
+        (_, Some(latitude_degree), Some(longitude_degree)) => Ok(QueryLocation::Coordinates {
+            latitude_degree,
+            longitude_degree,
+        }),
+        _ => Err(HvError::MalformedBatchRecord {
+            row,
+            reason: "either address or both lat and lon are required".to_owned(),
+        }),
+    };
+    BatchRow { id, location }
+}
+
+fn malformed_row(row: usize, reason: impl Display) -> BatchRow {
+    BatchRow {
+        id: row.to_string(),
+        location: Err(HvError::MalformedBatchRecord {
+            row,
+            reason: reason.to_string(),
+        }),
+    }
+}
+
+// a numeric id in jsonl is kept as written, e.g. `{"id": 7, ...}` is row "7"
+fn stringify_id(mut record: serde_json::Value) -> serde_json::Value {
+    if let Some(id) = record.get_mut("id") {
+        if id.is_number() {
+            *id = serde_json::Value::String(id.to_string());
+        }
+    }
+    record
+}
+
+// rows are numbered from 1, not counting the csv header.
+pub fn read_rows<R: Read>(reader: R, format: &BatchFormat) -> Vec<BatchRow> {
+    match format {
+        BatchFormat::Csv => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize::<RawBatchRecord>()
+            .enumerate()
+            .map(|(idx, record)| match record {
+                Ok(record) => to_batch_row(idx + 1, record),
+                Err(e) => malformed_row(idx + 1, e),
+            })
+            .collect(),
+        BatchFormat::Jsonl => BufReader::new(reader)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|(idx, line)| {
+                let record = line.map_err(|e| e.to_string()).and_then(|l| {
+                    serde_json::from_str::<serde_json::Value>(&l)
+                        .map(stringify_id)
+                        .and_then(serde_json::from_value::<RawBatchRecord>)
+                        .map_err(|e| e.to_string())
+                });
+                match record {
+                    Ok(record) => to_batch_row(idx + 1, record),
+                    Err(e) => malformed_row(idx + 1, e),
+                }
+            })
+            .collect(),
+    }
+}
+
+// Every row is queried with the same settings, given by `configure`, e.g. the search radius, the
+// distance model and the clearance policy.
+pub fn run_batch(
+    session: &mut QuerySession,
+    rows: Vec<BatchRow>,
+    configure: impl Fn(DistanceQuery) -> DistanceQuery,
+) -> Vec<BatchResult> {
+    rows.into_iter()
+        .map(|row| match row.location {
+            Ok(location) => {
+                let input = location.to_string();
+                let report = session.run(&configure(DistanceQuery::new(location)));
+                if let Err(e) = &report {
+                    warn!("failed to process row '{}': {}", row.id, e);
+                }
+                BatchResult {
+                    id: row.id,
+                    input,
+                    report,
+                }
+            }
+            Err(e) => {
+                warn!("failed to read row '{}': {}", row.id, e);
+                BatchResult {
+                    id: row.id,
+                    input: String::new(),
+                    report: Err(e),
+                }
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct JsonlOutputRow<'a> {
+    id: &'a str,
+    input: &'a str,
+    // voltage in kV -> distance in meter
+    distances: BTreeMap<u16, f64>,
+    // voltage in kV -> clearance band
+    bands: BTreeMap<u16, String>,
+    error: Option<String>,
+}
+
+pub fn write_results<W: Write>(
+    writer: W,
+    format: &BatchFormat,
+    results: &[BatchResult],
+) -> Result<()> {
+    match format {
+        BatchFormat::Csv => {
+            write_csv(writer, results).map_err(|e| HvError::BatchOutput(e.to_string()))
+        }
+        BatchFormat::Jsonl => {
+            write_jsonl(writer, results).map_err(|e| HvError::BatchOutput(e.to_string()))
+        }
+    }
+}
+
+fn write_csv<W: Write>(writer: W, results: &[BatchResult]) -> csv::Result<()> {
+    // voltages are different from row to row, every voltage found becomes a distance column
+    // followed by a band column.
+    let voltages = results
+        .iter()
+        .filter_map(|r| r.report.as_ref().ok())
+        .flat_map(|r| r.distances.iter().map(|d| d.voltage))
+        .collect::<BTreeSet<u16>>();
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    let mut header = vec!["id".to_owned(), "input".to_owned()];
+    header.extend(
+        voltages
+            .iter()
+            .rev()
+            .flat_map(|v| [format!("{}kV_m", v), format!("band_{}kV", v)]),
+    );
+    header.push("error".to_owned());
+    csv_writer.write_record(&header)?;
+    for result in results {
+        let mut record = vec![result.id.to_owned(), result.input.to_owned()];
+        match &result.report {
+            Ok(report) => {
+                record.extend(
+                    voltages
+                        .iter()
+                        .rev()
+                        .flat_map(|v| match report.distance_for(*v) {
+                            Some(d) => [format!("{:.0}", d.distance_m), d.band.to_string()],
+                            None => [String::new(), String::new()],
+                        }),
+                );
+                record.push(String::new());
+            }
+            Err(e) => {
+                record.extend(voltages.iter().flat_map(|_| [String::new(), String::new()]));
+                record.push(e.to_string());
+            }
+        }
+        csv_writer.write_record(&record)?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn write_jsonl<W: Write>(mut writer: W, results: &[BatchResult]) -> std::io::Result<()> {
+    for result in results {
+        let row = JsonlOutputRow {
+            id: &result.id,
+            input: &result.input,
+            distances: match &result.report {
+                Ok(report) => report
+                    .distances
+                    .iter()
+                    .map(|d| (d.voltage, d.distance_m))
+                    .collect(),
+                Err(_) => BTreeMap::new(),
+            },
+            bands: match &result.report {
+                Ok(report) => report
+                    .distances
+                    .iter()
+                    .map(|d| (d.voltage, d.band.to_string()))
+                    .collect(),
+                Err(_) => BTreeMap::new(),
+            },
+            error: result.report.as_ref().err().map(|e| e.to_string()),
+        };
+        serde_json::to_writer(&mut writer, &row)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test_read_rows {
+    use super::*;
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            BatchFormat::from_path(path::Path::new("a/b.csv")).unwrap(),
+            BatchFormat::Csv
+        );
+        assert_eq!(
+            BatchFormat::from_path(path::Path::new("b.jsonl")).unwrap(),
+            BatchFormat::Jsonl
+        );
+        assert!(matches!(
+            BatchFormat::from_path(path::Path::new("b.txt")),
+            Err(HvError::UnsupportedBatchFormat(_))
+        ));
+    }
+
+    #[test]
+    fn csv() {
+        let content = "id,address,lat,lon\n\
+                       a,\"56 Iris Street Frenchs Forest, NSW\",,\n\
+                       ,,-33.75,151.22\n\
+                       c,,-33.75,\n";
+        let rows = read_rows(content.as_bytes(), &BatchFormat::Csv);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].id, "a");
+        assert!(matches!(
+            &rows[0].location,
+            Ok(QueryLocation::Address(a)) if a == "56 Iris Street Frenchs Forest, NSW"
+        ));
+        assert_eq!(rows[1].id, "2");
+        assert!(matches!(
+            rows[1].location,
+            Ok(QueryLocation::Coordinates {
+                latitude_degree,
+                longitude_degree
+            }) if latitude_degree == -33.75 && longitude_degree == 151.22
+        ));
+        assert_eq!(rows[2].id, "c");
+        assert!(matches!(
+            rows[2].location,
+            Err(HvError::MalformedBatchRecord { row: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn csv_with_address_only() {
+        let content = "address\n1 George Street Sydney\n";
+        let rows = read_rows(content.as_bytes(), &BatchFormat::Csv);
+        assert_eq!(rows.len(), 1);
+        assert!(matches!(&rows[0].location, Ok(QueryLocation::Address(_))));
+    }
+
+    #[test]
+    fn jsonl_numeric_id() {
+        let content = "{\"id\": 7, \"address\": \"1 George Street Sydney\"}\n\
+                       {\"id\": 1.5, \"lat\": -33.75, \"lon\": 151.22}\n\
+                       {\"id\": null, \"lat\": -33.75, \"lon\": 151.22}\n";
+        let rows = read_rows(content.as_bytes(), &BatchFormat::Jsonl);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].id, "7");
+        assert!(matches!(&rows[0].location, Ok(QueryLocation::Address(_))));
+        assert_eq!(rows[1].id, "1.5");
+        assert!(rows[1].location.is_ok());
+        assert_eq!(rows[2].id, "3");
+    }
+
+    #[test]
+    fn jsonl() {
+        let content = "{\"id\": \"a\", \"address\": \"1 George Street Sydney\"}\n\
+                       \n\
+                       {\"lat\": -33.75, \"lon\": 151.22}\n\
+                       not a json\n";
+        let rows = read_rows(content.as_bytes(), &BatchFormat::Jsonl);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].id, "a");
+        assert!(matches!(&rows[0].location, Ok(QueryLocation::Address(_))));
+        assert_eq!(rows[1].id, "3");
+        assert!(matches!(
+            rows[1].location,
+            Ok(QueryLocation::Coordinates { .. })
+        ));
+        assert!(matches!(
+            rows[2].location,
+            Err(HvError::MalformedBatchRecord { row: 4, .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test_write_results {
+    use super::*;
+    use crate::geometry::{Point, Projection};
+    use crate::query::VoltageDistance;
+
+    fn results() -> Vec<BatchResult> {
+        let report = |distances: Vec<(u16, f64)>| DistanceReport {
+            full_address: None,
+            position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            projection: Projection::default(),
+            location: Point { x: 0.0, y: 0.0 },
+            lot: None,
+            distances: distances
+                .into_iter()
+                .map(|(voltage, distance_m)| {
+                    VoltageDistance::new_for_test(voltage, distance_m, "1")
+                })
+                .collect(),
+            suburb: None,
+            suburbs_searched: vec![],
+            searched_radius_m: 0.0,
+        };
+        vec![
+            BatchResult {
+                id: "a".to_owned(),
+                input: "-33.75,151.22".to_owned(),
+                report: Ok(report(vec![(330, 1884.2), (132, 548.0)])),
+            },
+            BatchResult {
+                id: "b".to_owned(),
+                input: "-33.76,151.22".to_owned(),
+                report: Ok(report(vec![(66, 12.0)])),
+            },
+            BatchResult {
+                id: "c".to_owned(),
+                input: "nowhere".to_owned(),
+                report: Err(HvError::GeocodeNotFound("nowhere".to_owned())),
+            },
+        ]
+    }
+
+    #[test]
+    fn csv() {
+        let mut buf = Vec::<u8>::new();
+        write_results(&mut buf, &BatchFormat::Csv, &results()).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "id,input,330kV_m,band_330kV,132kV_m,band_132kV,66kV_m,band_66kV,error\n\
+             a,\"-33.75,151.22\",1884,outside 300m,548,outside 200m,,,\n\
+             b,\"-33.76,151.22\",,,,,12,within 20m,\n\
+             c,nowhere,,,,,,,no result found for address 'nowhere'\n"
+        );
+    }
+
+    #[test]
+    fn jsonl() {
+        let mut buf = Vec::<u8>::new();
+        write_results(&mut buf, &BatchFormat::Jsonl, &results()).unwrap();
+        let lines = String::from_utf8(buf).unwrap();
+        let lines = lines.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            r#"{"id":"a","input":"-33.75,151.22","distances":{"132":548.0,"330":1884.2},"bands":{"132":"outside 200m","330":"outside 300m"},"error":null}"#
+        );
+        assert_eq!(
+            lines[2],
+            r#"{"id":"c","input":"nowhere","distances":{},"bands":{},"error":"no result found for address 'nowhere'"}"#
+        );
+    }
+}
@@ -1,28 +1,67 @@
 use std::collections::HashMap;
 
 use crate::api::geocode::Address;
-use crate::api::property_data_map::SelectSuburbResponse;
+use crate::api::property_data_map::{SelectSuburbResponse, SelectedLatLon, SuburbPolygon};
+use crate::error::{HvError, Result};
 use crate::geometry;
 
-fn raw_position_to_point(latitude_degree: f64, longitude_degree: f64) -> geometry::Point {
-    geometry::GeoPosition {
-        latitude_radius: latitude_degree.to_radians(),
-        longitude_radius: longitude_degree.to_radians(),
-    }
-    .to_cartesian()
+fn raw_position_to_point(
+    latitude_degree: f64,
+    longitude_degree: f64,
+    projection: &geometry::Projection,
+) -> geometry::Point {
+    projection.project(&geometry::GeoPosition::from_degree(
+        latitude_degree,
+        longitude_degree,
+    ))
 }
 
 pub struct SuburbInfo {
     pub name: String,
     pub id: u16,
     pub postcode: u16,
+    pub position: geometry::GeoPosition,
+    // position projected with the projection given to `get_all_suburbs`
     pub location: geometry::Point,
 }
 
-// suburb id is for propertydatamap.com
+fn parse_suburb_field<T: std::str::FromStr>(code: &str, field: &str, raw: &str) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    raw.parse::<T>()
+        .map_err(|e| HvError::MalformedSuburbRecord {
+            code: code.to_owned(),
+            reason: format!("failed to parse {}, got '{}'\n{}", field, raw, e),
+        })
+}
+
+fn parse_suburb(
+    code_str: &str,
+    raw_suburb_code_info: &[String; 4],
+    projection: &geometry::Projection,
+) -> Result<SuburbInfo> {
+    let code = parse_suburb_field::<u16>(code_str, "code", code_str)?;
+    let postcode =
+        parse_suburb_field::<u16>(code_str, "suburb_postcode", &raw_suburb_code_info[1])?;
+    let latitude = parse_suburb_field::<f64>(code_str, "latitude", &raw_suburb_code_info[2])?;
+    let longitude = parse_suburb_field::<f64>(code_str, "longitude", &raw_suburb_code_info[3])?;
+    let position = geometry::GeoPosition::from_degree(latitude, longitude);
+    Ok(SuburbInfo {
+        name: raw_suburb_code_info[0].to_owned(),
+        id: code,
+        postcode,
+        location: projection.project(&position),
+        position,
+    })
+}
+
+// suburb id is for propertydatamap.com. A malformed record is skipped with a warning, so that one
+// bad suburb doesn't stop every query.
 pub fn get_all_suburbs(
-    raw_suburb_map: HashMap<String, [String; 4]>,
-    // suburb id -> suburb location
+    // suburb id -> [name, postcode, latitude, longitude]
+    raw_suburb_map: &HashMap<String, [String; 4]>,
+    projection: &geometry::Projection,
 ) -> Vec<SuburbInfo> {
     let mut suburb_info = Vec::<SuburbInfo>::new();
     for (code_str, raw_suburb_code_info) in raw_suburb_map {
@@ -30,107 +69,190 @@ pub fn get_all_suburbs(
         if raw_suburb_code_info[1] == "None" {
             continue;
         }
-        let code = match code_str.parse::<u16>() {
-            Ok(val) => val,
-            Err(e) => panic!(
-                "failed to parse code in raw_suburb_map, expect u16 but got '{}'\n{}",
-                code_str, e
-            ),
-        };
-        let postcode = match raw_suburb_code_info[1].parse::<u16>() {
-            Ok(val) => val,
-            Err(e) => panic!(
-                "failed to parse suburb_postcode in raw_suburb_map, expect u16 but got '{}'\n{}",
-                raw_suburb_code_info[1], e
-            ),
-        };
-        let latitude = match raw_suburb_code_info[2].parse::<f64>() {
-            Ok(val) => val,
-            Err(e) => panic!(
-                "failed to parse latitude, expect f64 but got '{}'\n{}",
-                raw_suburb_code_info[2], e
-            ),
-        };
-        let longitude = match raw_suburb_code_info[3].parse::<f64>() {
-            Ok(val) => val,
-            Err(e) => panic!(
-                "failed to parse longitude, expect f64 but got '{}'\n{}",
-                raw_suburb_code_info[3], e
-            ),
-        };
-        suburb_info.push(SuburbInfo {
-            name: raw_suburb_code_info[0].to_owned(),
-            id: code,
-            postcode: postcode,
-            location: raw_position_to_point(latitude, longitude),
-        });
+        match parse_suburb(code_str, raw_suburb_code_info, projection) {
+            Ok(info) => suburb_info.push(info),
+            Err(e) => warn!("skip suburb '{}': {}", code_str, e),
+        }
     }
     suburb_info
 }
 
-pub fn parse_address(address: Address) -> geometry::Point {
-    geometry::GeoPosition {
-        latitude_radius: address.latitude_degree.to_radians(),
-        longitude_radius: address.longitude_degree.to_radians(),
+pub fn parse_address(address: Address, projection: &geometry::Projection) -> geometry::Point {
+    raw_position_to_point(
+        address.latitude_degree,
+        address.longitude_degree,
+        projection,
+    )
+}
+
+// rough bounding box of NSW (and ACT), which is what the propertydatamap suburb map covers.
+const NSW_LATITUDE_RANGE: (f64, f64) = (-37.6, -28.1);
+const NSW_LONGITUDE_RANGE: (f64, f64) = (140.9, 153.7);
+
+pub fn is_within_nsw(latitude_degree: f64, longitude_degree: f64) -> bool {
+    latitude_degree >= NSW_LATITUDE_RANGE.0
+        && latitude_degree <= NSW_LATITUDE_RANGE.1
+        && longitude_degree >= NSW_LONGITUDE_RANGE.0
+        && longitude_degree <= NSW_LONGITUDE_RANGE.1
+}
+
+pub fn parse_coordinates(
+    latitude_degree: f64,
+    longitude_degree: f64,
+    projection: &geometry::Projection,
+) -> Result<geometry::Point> {
+    if !is_within_nsw(latitude_degree, longitude_degree) {
+        return Err(HvError::OutOfCoverage {
+            latitude_degree,
+            longitude_degree,
+        });
+    }
+    Ok(raw_position_to_point(
+        latitude_degree,
+        longitude_degree,
+        projection,
+    ))
+}
+
+// only the outer ring is used, suburbs don't have holes.
+pub fn parse_suburb_boundary(
+    suburb_id: u16,
+    raw: &SuburbPolygon,
+    projection: &geometry::Projection,
+) -> Result<geometry::Polygon> {
+    if raw.r#type != "Polygon" {
+        return Err(HvError::UnsupportedGeometry(raw.r#type.to_owned()));
     }
-    .to_cartesian()
+    let points = match raw.coordinates.first() {
+        // 3 distinct points with the ring closed
+        Some(ring) if ring.len() >= 4 => ring
+            .iter()
+            .map(|p| raw_position_to_point(p[1], p[0], projection))
+            .collect::<Vec<geometry::Point>>(),
+        _ => {
+            return Err(HvError::MalformedSuburbRecord {
+                code: suburb_id.to_string(),
+                reason: "boundary needs at least 3 points".to_owned(),
+            })
+        }
+    };
+    geometry::Polygon::new(points).map_err(|e| HvError::MalformedSuburbRecord {
+        code: suburb_id.to_string(),
+        reason: e.to_string(),
+    })
 }
 
+// a span between two poles is usually within a few hundred meters
+const INDEX_CELL_SIZE_M: f64 = 250.0;
+
+#[derive(Clone)]
 pub struct HighVoltageLine {
     pub line: geometry::PolyLine,
+    // same vertices as `line`, before being projected
+    pub positions: Vec<geometry::GeoPosition>,
     pub id: String,
+    // the suburb the line is fetched from, a line can be in many suburbs
+    pub suburb_id: u16,
 }
 
-pub fn parse_high_voltage_lines(raw: SelectSuburbResponse) -> HashMap<u16, Vec<HighVoltageLine>> {
-    let mut high_voltage_lines = HashMap::<u16, Vec<HighVoltageLine>>::new();
-    let lines_map = raw.selected_lat_lon;
-    let voltages_map = raw.selected_popup_info;
-    for (line_id, line) in lines_map {
-        if line.r#type != "LineString" {
-            panic!(
-                "only LineString is supported for lines, but got '{}'",
-                line.r#type
-            );
-        }
-        let points = line
-            .coordinates
-            .into_iter()
-            .map(|p| raw_position_to_point(p[1], p[0]))
-            .collect::<Vec<geometry::Point>>();
-        let voltage_str = match voltages_map.get(&line_id) {
-            Some(val) => val,
-            None => panic!(
-                "can not find voltage for id='{line_id}' in the map {:?}",
-                voltages_map
-            ),
-        };
-        if voltage_str.len() > 1 {
-            panic!(
-                "only 1 voltage should be in the map, but got '{:?}'",
-                voltage_str
-            )
+impl HighVoltageLine {
+    pub fn closest_point_to(&self, point: &geometry::Point) -> geometry::ClosestPoint {
+        self.line.closest_point_to(point)
+    }
+
+    pub fn distance_to(
+        &self,
+        point: &geometry::Point,
+        position: &geometry::GeoPosition,
+        model: &geometry::DistanceModel,
+    ) -> f64 {
+        match model {
+            geometry::DistanceModel::Planar => self.line.distance_to(point),
+            _ => geometry::geodesic::distance_to_polyline(position, &self.positions, model),
         }
-        let voltage = match voltage_str[0].replace("kV", "").parse::<u16>() {
-            Ok(val) => val,
-            Err(e) => panic!(
-                "failed to parsed voltage string '{}' to u8\n{}",
-                voltage_str[0], e
-            ),
-        };
-        match high_voltage_lines.get_mut(&voltage) {
-            Some(arr) => arr.push(HighVoltageLine {
-                line: geometry::PolyLine::new(points),
-                id: line_id,
-            }),
-            None => {
-                high_voltage_lines.insert(
-                    voltage,
-                    vec![HighVoltageLine {
-                        line: geometry::PolyLine::new(points),
-                        id: line_id,
-                    }],
-                );
+    }
+}
+
+// every span of the lines of a voltage, pointing back to the index of its line and of the span
+pub type LineIndex = geometry::SegmentIndex<(usize, usize)>;
+
+pub fn index_high_voltage_lines(
+    high_voltage_lines: &HashMap<u16, Vec<HighVoltageLine>>,
+) -> HashMap<u16, LineIndex> {
+    high_voltage_lines
+        .iter()
+        .map(|(voltage, lines)| {
+            let mut index = geometry::SegmentIndex::new(INDEX_CELL_SIZE_M);
+            for (line_idx, line) in lines.iter().enumerate() {
+                for (segment_idx, segment) in line.line.segments().iter().enumerate() {
+                    index.insert(segment.clone(), (line_idx, segment_idx));
+                }
             }
+            (*voltage, index)
+        })
+        .collect()
+}
+
+fn parse_high_voltage_line(
+    suburb_id: u16,
+    line_id: &str,
+    line: &SelectedLatLon,
+    voltages: Option<&Vec<String>>,
+    projection: &geometry::Projection,
+) -> Result<(u16, HighVoltageLine)> {
+    if line.r#type != "LineString" {
+        return Err(HvError::UnsupportedGeometry(line.r#type.to_owned()));
+    }
+    let positions = line
+        .coordinates
+        .iter()
+        .map(|p| geometry::GeoPosition::from_degree(p[1], p[0]))
+        .collect::<Vec<geometry::GeoPosition>>();
+    let points = positions
+        .iter()
+        .map(|p| projection.project(p))
+        .collect::<Vec<geometry::Point>>();
+    let voltage_str = match voltages {
+        Some(val) if !val.is_empty() => val,
+        _ => return Err(HvError::MissingVoltage(line_id.to_owned())),
+    };
+    if voltage_str.len() > 1 {
+        return Err(HvError::MultipleVoltages {
+            line_id: line_id.to_owned(),
+            voltages: voltage_str.to_owned(),
+        });
+    }
+    let voltage = match voltage_str[0].replace("kV", "").parse::<u16>() {
+        Ok(val) => val,
+        Err(_) => return Err(HvError::InvalidVoltage(voltage_str[0].to_owned())),
+    };
+    let line = geometry::PolyLine::new(points).map_err(|source| HvError::InvalidGeometry {
+        id: line_id.to_owned(),
+        source,
+    })?;
+    Ok((
+        voltage,
+        HighVoltageLine {
+            line,
+            positions,
+            id: line_id.to_owned(),
+            suburb_id,
+        },
+    ))
+}
+
+// A malformed line is skipped with a warning, the other lines of the suburb are still searched.
+pub fn parse_high_voltage_lines(
+    suburb_id: u16,
+    raw: &SelectSuburbResponse,
+    projection: &geometry::Projection,
+) -> HashMap<u16, Vec<HighVoltageLine>> {
+    let mut high_voltage_lines = HashMap::<u16, Vec<HighVoltageLine>>::new();
+    for (line_id, line) in &raw.selected_lat_lon {
+        let voltages = raw.selected_popup_info.get(line_id);
+        match parse_high_voltage_line(suburb_id, line_id, line, voltages, projection) {
+            Ok((voltage, line)) => high_voltage_lines.entry(voltage).or_default().push(line),
+            Err(e) => warn!("skip line '{}' of suburb {}: {}", line_id, suburb_id, e),
         }
     }
     high_voltage_lines
@@ -162,7 +284,7 @@ mod tests_init_suburbs {
                 ],
             ),
         ]);
-        let info = get_all_suburbs(raw_suburb_map);
+        let info = get_all_suburbs(&raw_suburb_map, &geometry::Projection::default());
         assert_eq!(info.len(), 2);
         let cherrybrook_idx = if info[0].postcode == 2126 { 0 } else { 1 };
         let west_ryde_idx = 1 - cherrybrook_idx;
@@ -210,7 +332,7 @@ mod tests_init_suburbs {
                 ],
             ),
         ]);
-        let info = get_all_suburbs(raw_suburb_map);
+        let info = get_all_suburbs(&raw_suburb_map, &geometry::Projection::default());
         assert_eq!(info.len(), 1);
         assert_eq!(info[0].name, "CHERRYBROOK");
         assert_eq!(info[0].postcode, 2126);
@@ -225,7 +347,6 @@ mod tests_init_suburbs {
     }
 
     #[test]
-    #[should_panic]
     fn throw_on_non_number_code() {
         let raw_suburb_map: HashMap<String, [String; 4]> = HashMap::from([(
             "abcd".to_owned(),
@@ -236,11 +357,14 @@ mod tests_init_suburbs {
                 "151.04624440456263".to_owned(),
             ],
         )]);
-        get_all_suburbs(raw_suburb_map);
+        let (code, raw) = raw_suburb_map.iter().next().unwrap();
+        assert!(matches!(
+            parse_suburb(code, raw, &geometry::Projection::default()),
+            Err(HvError::MalformedSuburbRecord { .. })
+        ));
     }
 
     #[test]
-    #[should_panic]
     fn throw_on_non_number_postcode() {
         let raw_suburb_map: HashMap<String, [String; 4]> = HashMap::from([(
             "3900".to_owned(),
@@ -251,7 +375,47 @@ mod tests_init_suburbs {
                 "151.04624440456263".to_owned(),
             ],
         )]);
-        get_all_suburbs(raw_suburb_map);
+        let (code, raw) = raw_suburb_map.iter().next().unwrap();
+        assert!(matches!(
+            parse_suburb(code, raw, &geometry::Projection::default()),
+            Err(HvError::MalformedSuburbRecord { .. })
+        ));
+    }
+
+    #[test]
+    fn skip_malformed_record() {
+        let raw_suburb_map: HashMap<String, [String; 4]> = HashMap::from([
+            (
+                "3900".to_owned(),
+                [
+                    "CHERRYBROOK".to_owned(),
+                    "2126".to_owned(),
+                    "-33.72185040017101".to_owned(),
+                    "151.04624440456263".to_owned(),
+                ],
+            ),
+            (
+                "371".to_owned(),
+                [
+                    "WEST RYDE".to_owned(),
+                    "2114".to_owned(),
+                    "not a latitude".to_owned(),
+                    "151.08385175565996".to_owned(),
+                ],
+            ),
+            (
+                "abcd".to_owned(),
+                [
+                    "EPPING".to_owned(),
+                    "2121".to_owned(),
+                    "-33.7727".to_owned(),
+                    "151.0818".to_owned(),
+                ],
+            ),
+        ]);
+        let info = get_all_suburbs(&raw_suburb_map, &geometry::Projection::default());
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0].name, "CHERRYBROOK");
     }
 }
 
@@ -266,7 +430,7 @@ mod test_parse_address {
             latitude_degree: -33.921119441679096,
             longitude_degree: 151.1984099658811,
         };
-        let location = parse_address(address);
+        let location = parse_address(address, &geometry::Projection::default());
         location.assert_close_to(
             &geometry::Point {
                 x: -738.6767504988909,
@@ -277,6 +441,71 @@ mod test_parse_address {
     }
 }
 
+#[cfg(test)]
+mod test_parse_coordinates {
+    use super::*;
+
+    #[test]
+    fn success() {
+        let location = parse_coordinates(
+            -33.921119441679096,
+            151.1984099658811,
+            &geometry::Projection::default(),
+        )
+        .unwrap();
+        location.assert_close_to(
+            &geometry::Point {
+                x: -738.6767504988909,
+                y: -4301.452856541296,
+            },
+            1.0,
+        )
+    }
+
+    #[test]
+    fn success_with_projection_at_the_point() {
+        let projection = geometry::Projection::new(
+            geometry::GeoPosition::from_degree(-32.9283, 151.7817),
+            geometry::ProjectionMethod::Equirectangular,
+        );
+        let location = parse_coordinates(-32.9283, 151.7817, &projection).unwrap();
+        location.assert_close_to(&geometry::Point { x: 0.0, y: 0.0 }, 1e-9)
+    }
+
+    #[test]
+    fn failed_outside_nsw() {
+        // Melbourne
+        assert!(matches!(
+            parse_coordinates(-37.8136, 144.9631, &geometry::Projection::default()),
+            Err(HvError::OutOfCoverage { .. })
+        ));
+    }
+
+    #[test]
+    fn failed_with_swapped_lat_lon() {
+        assert!(matches!(
+            parse_coordinates(
+                151.1984099658811,
+                -33.921119441679096,
+                &geometry::Projection::default()
+            ),
+            Err(HvError::OutOfCoverage { .. })
+        ));
+    }
+
+    #[test]
+    fn failed_with_nan() {
+        assert!(matches!(
+            parse_coordinates(
+                f64::NAN,
+                151.1984099658811,
+                &geometry::Projection::default()
+            ),
+            Err(HvError::OutOfCoverage { .. })
+        ));
+    }
+}
+
 #[cfg(test)]
 mod test_parse_high_voltage_lines {
     use super::*;
@@ -285,6 +514,16 @@ mod test_parse_high_voltage_lines {
         geometry::{Point, PolyLine},
     };
 
+    fn parse_line_512(raw: &SelectSuburbResponse) -> Result<(u16, HighVoltageLine)> {
+        parse_high_voltage_line(
+            1,
+            "512",
+            &raw.selected_lat_lon["512"],
+            raw.selected_popup_info.get("512"),
+            &geometry::Projection::default(),
+        )
+    }
+
     #[test]
     fn success() {
         let raw_response = SelectSuburbResponse {
@@ -325,8 +564,10 @@ mod test_parse_high_voltage_lines {
                 ("1024".to_owned(), vec!["123kV".to_owned()]),
                 ("2048".to_owned(), vec!["66kV".to_owned()]),
             ]),
+            boundary: None,
         };
-        let high_voltage_lines = parse_high_voltage_lines(raw_response);
+        let high_voltage_lines =
+            parse_high_voltage_lines(1, &raw_response, &geometry::Projection::default());
         let v66kv = high_voltage_lines.get(&66).unwrap();
         assert_eq!(v66kv.len(), 1);
         assert_eq!(v66kv[0].id, "2048");
@@ -340,7 +581,8 @@ mod test_parse_high_voltage_lines {
                     x: 7513.165838856347,
                     y: 9962.083813063693,
                 },
-            ]),
+            ])
+            .unwrap(),
             1.0,
         );
         let v123kv = high_voltage_lines.get(&123).unwrap();
@@ -358,7 +600,8 @@ mod test_parse_high_voltage_lines {
                     x: -22787.159185405268,
                     y: 18391.717575661813,
                 },
-            ]),
+            ])
+            .unwrap(),
             1.0,
         );
         assert_eq!(v123kv[line_512_idx].id, "512");
@@ -372,14 +615,14 @@ mod test_parse_high_voltage_lines {
                     x: -22787.159185405268,
                     y: 18391.717575661813,
                 },
-            ]),
+            ])
+            .unwrap(),
             1.0,
         );
         assert_eq!(v123kv[line_1024_idx].id, "1024");
     }
 
     #[test]
-    #[should_panic(expected = "failed to parsed voltage string '123KV' to u8")]
     fn failed_with_wrong_voltage_format() {
         let raw_response = SelectSuburbResponse {
             selected_lat_lon: HashMap::from([(
@@ -390,12 +633,15 @@ mod test_parse_high_voltage_lines {
                 },
             )]),
             selected_popup_info: HashMap::from([("512".to_owned(), vec!["123KV".to_owned()])]),
+            boundary: None,
         };
-        parse_high_voltage_lines(raw_response);
+        assert!(matches!(
+            parse_line_512(&raw_response),
+            Err(HvError::InvalidVoltage(v)) if v == "123KV"
+        ));
     }
 
     #[test]
-    #[should_panic(expected = "only 1 voltage should be in the map, but got")]
     fn failed_with_multiple_voltage() {
         let raw_response = SelectSuburbResponse {
             selected_lat_lon: HashMap::from([(
@@ -409,12 +655,15 @@ mod test_parse_high_voltage_lines {
                 "512".to_owned(),
                 vec!["123KV".to_owned(), "123KV".to_owned()],
             )]),
+            boundary: None,
         };
-        parse_high_voltage_lines(raw_response);
+        assert!(matches!(
+            parse_line_512(&raw_response),
+            Err(HvError::MultipleVoltages { .. })
+        ));
     }
 
     #[test]
-    #[should_panic(expected = "only LineString is supported for lines, but got 'PolyLine'")]
     fn failed_with_unsupported_type() {
         let raw_response = SelectSuburbResponse {
             selected_lat_lon: HashMap::from([(
@@ -425,12 +674,15 @@ mod test_parse_high_voltage_lines {
                 },
             )]),
             selected_popup_info: HashMap::from([("512".to_owned(), vec!["123kV".to_owned()])]),
+            boundary: None,
         };
-        parse_high_voltage_lines(raw_response);
+        assert!(matches!(
+            parse_line_512(&raw_response),
+            Err(HvError::UnsupportedGeometry(t)) if t == "PolyLine"
+        ));
     }
 
     #[test]
-    #[should_panic(expected = "can not find voltage for id='512' in the map")]
     fn failed_with_missing_line() {
         let raw_response = SelectSuburbResponse {
             selected_lat_lon: HashMap::from([(
@@ -441,7 +693,130 @@ mod test_parse_high_voltage_lines {
                 },
             )]),
             selected_popup_info: HashMap::from([("1024".to_owned(), vec!["123kV".to_owned()])]),
+            boundary: None,
         };
-        parse_high_voltage_lines(raw_response);
+        assert!(matches!(
+            parse_line_512(&raw_response),
+            Err(HvError::MissingVoltage(id)) if id == "512"
+        ));
+    }
+
+    #[test]
+    fn failed_with_repeated_point() {
+        let raw_response = SelectSuburbResponse {
+            selected_lat_lon: HashMap::from([(
+                "512".to_owned(),
+                SelectedLatLon {
+                    r#type: "LineString".to_owned(),
+                    coordinates: vec![
+                        [151.1984099658811, -33.921119441679096, 0.0],
+                        [151.1984099658811, -33.921119441679096, 0.0],
+                        [150.9600398224331, -33.71703513789143, 0.0],
+                    ],
+                },
+            )]),
+            selected_popup_info: HashMap::from([("512".to_owned(), vec!["123kV".to_owned()])]),
+            boundary: None,
+        };
+        assert!(matches!(
+            parse_line_512(&raw_response),
+            Err(HvError::InvalidGeometry { id, .. }) if id == "512"
+        ));
+    }
+
+    #[test]
+    fn skip_malformed_line() {
+        let line = |r#type: &str| SelectedLatLon {
+            r#type: r#type.to_owned(),
+            coordinates: vec![
+                [151.1984099658811, -33.921119441679096, 0.0],
+                [150.9600398224331, -33.71703513789143, 0.0],
+            ],
+        };
+        let raw_response = SelectSuburbResponse {
+            selected_lat_lon: HashMap::from([
+                ("512".to_owned(), line("LineString")),
+                ("1024".to_owned(), line("Point")),
+                ("2048".to_owned(), line("LineString")),
+                ("4096".to_owned(), line("LineString")),
+            ]),
+            selected_popup_info: HashMap::from([
+                ("512".to_owned(), vec!["123kV".to_owned()]),
+                ("1024".to_owned(), vec!["123kV".to_owned()]),
+                (
+                    "2048".to_owned(),
+                    vec!["66kV".to_owned(), "33kV".to_owned()],
+                ),
+            ]),
+            boundary: None,
+        };
+        let high_voltage_lines =
+            parse_high_voltage_lines(1, &raw_response, &geometry::Projection::default());
+        assert_eq!(high_voltage_lines.len(), 1);
+        let v123kv = high_voltage_lines.get(&123).unwrap();
+        assert_eq!(v123kv.len(), 1);
+        assert_eq!(v123kv[0].id, "512");
+    }
+}
+
+#[cfg(test)]
+mod tests_parse_suburb_boundary {
+    use super::*;
+    use crate::geometry::Point;
+
+    fn raw(r#type: &str, ring: Vec<[f64; 2]>) -> SuburbPolygon {
+        SuburbPolygon {
+            r#type: r#type.to_owned(),
+            coordinates: vec![ring],
+        }
+    }
+
+    #[test]
+    fn success() {
+        let projection = geometry::Projection::new(
+            geometry::GeoPosition::from_degree(-33.75, 151.25),
+            geometry::ProjectionMethod::Sinusoidal,
+        );
+        let boundary = parse_suburb_boundary(
+            1,
+            &raw(
+                "Polygon",
+                vec![
+                    [151.2, -33.7],
+                    [151.3, -33.7],
+                    [151.3, -33.8],
+                    [151.2, -33.8],
+                    [151.2, -33.7],
+                ],
+            ),
+            &projection,
+        )
+        .unwrap();
+        assert_eq!(boundary.get_vertices().len(), 4);
+        assert!(boundary.contains(&Point { x: 0.0, y: 0.0 }));
+        assert!(!boundary.contains(&Point {
+            x: 10_000.0,
+            y: 0.0
+        }));
+    }
+
+    #[test]
+    fn failed_with_multi_polygon() {
+        assert!(matches!(
+            parse_suburb_boundary(1, &raw("MultiPolygon", vec![]), &geometry::Projection::default()),
+            Err(HvError::UnsupportedGeometry(t)) if t == "MultiPolygon"
+        ));
+    }
+
+    #[test]
+    fn failed_with_too_few_points() {
+        assert!(matches!(
+            parse_suburb_boundary(
+                7,
+                &raw("Polygon", vec![[151.2, -33.7], [151.3, -33.7], [151.2, -33.7]]),
+                &geometry::Projection::default()
+            ),
+            Err(HvError::MalformedSuburbRecord { code, .. }) if code == "7"
+        ));
     }
 }
@@ -0,0 +1,341 @@
+use std::rc::Rc;
+
+use crate::api::geocode::Address;
+use crate::data_source::SuburbInfo;
+use crate::error::{HvError, Result};
+use crate::geometry::geodesic::haversine_distance;
+use crate::geometry::GeoPosition;
+
+// candidates this close to each other are the same place, e.g. a building and its address
+const SAME_PLACE_M: f64 = 200.0;
+const STATES: [&str; 8] = ["nsw", "act", "vic", "qld", "sa", "wa", "tas", "nt"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddressCandidate {
+    pub address: Address,
+    // the suburb in the suburb table the address is in, as named in the table
+    pub suburb: Option<String>,
+    pub postcode: Option<u16>,
+    // higher is better
+    pub score: u8,
+}
+
+// given the address and the candidates from the best, `None` gives up
+pub type PickCandidate = dyn Fn(&str, &[AddressCandidate]) -> Option<usize>;
+
+// What to do when the geocoder finds more than one place for an address.
+#[derive(Clone, Default)]
+pub enum Disambiguation {
+    // the best scored candidate, with a warning
+    #[default]
+    Best,
+    // fails unless one candidate is scored better than the others
+    Strict,
+    // asks which candidate to use
+    Pick(Rc<PickCandidate>),
+}
+
+fn is_postcode(word: &str) -> bool {
+    word.len() == 4 && word.chars().all(|c| c.is_ascii_digit())
+}
+
+// the last postcode in the address, as in "..., New South Wales, 2126, Australia"
+fn find_postcode(full_address: &str) -> Option<u16> {
+    full_address
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .rfind(|w| is_postcode(w))
+        .and_then(|w| w.parse().ok())
+}
+
+// A part of the address between commas without the state and postcode, so that both
+// "Frenchs Forest" and "Frenchs Forest NSW 2086" are "frenchs forest".
+fn place_name(part: &str) -> String {
+    part.split_whitespace()
+        .map(|w| w.to_lowercase())
+        .filter(|w| !is_postcode(w) && !STATES.contains(&w.as_str()))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// The suburb in the table named in the address, preferring the one with the same postcode, since
+// the same name can be used by suburbs in different councils.
+fn find_suburb<'a>(
+    full_address: &str,
+    postcode: Option<u16>,
+    suburbs: &'a [SuburbInfo],
+) -> Option<&'a SuburbInfo> {
+    let names = full_address
+        .split(',')
+        .map(place_name)
+        .filter(|n| !n.is_empty())
+        .collect::<Vec<String>>();
+    let mut found = suburbs
+        .iter()
+        .filter(|s| names.iter().any(|n| s.name.eq_ignore_ascii_case(n)));
+    let first = found.clone().next();
+    found.find(|s| Some(s.postcode) == postcode).or(first)
+}
+
+// Candidates are scored by
+// - 1 when its suburb or postcode is in the suburb table, 2 when both and they agree.
+// - 2 when its suburb or postcode is also in the address looked up.
+// Candidates with the same score are kept in the order of the geocoder.
+pub fn score_candidates(
+    query: &str,
+    candidates: Vec<Address>,
+    suburbs: &[SuburbInfo],
+) -> Vec<AddressCandidate> {
+    let query_words = place_name(&query.replace(',', " "));
+    let query_postcode = find_postcode(query);
+    let mut scored = candidates
+        .into_iter()
+        .map(|address| {
+            let postcode = find_postcode(&address.full_address);
+            let suburb = find_suburb(&address.full_address, postcode, suburbs);
+            let postcode_known = postcode.is_some_and(|p| suburbs.iter().any(|s| s.postcode == p));
+            let mut score = match (suburb, postcode) {
+                (Some(s), Some(p)) if s.postcode == p => 2,
+                (Some(_), _) => 1,
+                (None, _) if postcode_known => 1,
+                _ => 0,
+            };
+            let suburb_in_query = suburb.is_some_and(|s| {
+                format!(" {} ", query_words).contains(&format!(" {} ", s.name.to_lowercase()))
+            });
+            if suburb_in_query || (postcode.is_some() && postcode == query_postcode) {
+                score += 2;
+            }
+            AddressCandidate {
+                suburb: suburb.map(|s| s.name.to_owned()),
+                postcode,
+                address,
+                score,
+            }
+        })
+        .collect::<Vec<AddressCandidate>>();
+    scored.sort_by_key(|c| std::cmp::Reverse(c.score));
+    scored
+}
+
+fn position(address: &Address) -> GeoPosition {
+    GeoPosition::from_degree(address.latitude_degree, address.longitude_degree)
+}
+
+// Other candidates as good as the best one, which are not the same place.
+fn rivals(candidates: &[AddressCandidate]) -> usize {
+    let best = &candidates[0];
+    candidates[1..]
+        .iter()
+        .filter(|c| {
+            c.score == best.score
+                && haversine_distance(&position(&c.address), &position(&best.address))
+                    > SAME_PLACE_M
+        })
+        .count()
+}
+
+pub fn resolve(
+    query: &str,
+    candidates: Vec<Address>,
+    suburbs: &[SuburbInfo],
+    disambiguation: &Disambiguation,
+) -> Result<Address> {
+    if candidates.is_empty() {
+        return Err(HvError::GeocodeNotFound(query.to_owned()));
+    }
+    let mut candidates = score_candidates(query, candidates, suburbs);
+    if candidates.len() == 1 {
+        return Ok(candidates.swap_remove(0).address);
+    }
+    let ambiguous = || HvError::AmbiguousAddress {
+        address: query.to_owned(),
+        candidates: candidates
+            .iter()
+            .map(|c| c.address.full_address.to_owned())
+            .collect(),
+    };
+    let picked = match disambiguation {
+        Disambiguation::Best => {
+            if rivals(&candidates) > 0 {
+                warn!(
+                    "more than one results found for address '{}', results are: '{:#?}'.",
+                    query, candidates
+                );
+                warn!("The best matched address will be used, if it's not expected, please specify more specific address, or use --pick");
+            }
+            0
+        }
+        Disambiguation::Strict => {
+            if rivals(&candidates) > 0 {
+                return Err(ambiguous());
+            }
+            0
+        }
+        Disambiguation::Pick(pick) => match pick(query, &candidates) {
+            Some(idx) if idx < candidates.len() => idx,
+            _ => return Err(ambiguous()),
+        },
+    };
+    let picked = candidates.swap_remove(picked);
+    debug!(
+        "'{}' picked with score {}, in suburb {:?}",
+        picked.address.full_address, picked.score, picked.suburb
+    );
+    Ok(picked.address)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Point;
+
+    fn suburb(name: &str, postcode: u16) -> SuburbInfo {
+        SuburbInfo {
+            name: name.to_owned(),
+            id: postcode,
+            postcode,
+            position: GeoPosition::from_degree(-33.8, 151.0),
+            location: Point { x: 0.0, y: 0.0 },
+        }
+    }
+
+    fn suburbs() -> Vec<SuburbInfo> {
+        vec![
+            suburb("FRENCHS FOREST", 2086),
+            suburb("BEACON HILL", 2100),
+            suburb("CHERRYBROOK", 2126),
+        ]
+    }
+
+    fn address(full_address: &str, latitude_degree: f64) -> Address {
+        Address {
+            full_address: full_address.to_owned(),
+            latitude_degree,
+            longitude_degree: 151.2,
+        }
+    }
+
+    fn iris_streets() -> Vec<Address> {
+        vec![
+            address(
+                "56, Iris Street, Mudgee, Mid-Western Regional Council, New South Wales, 2850, Australia",
+                -32.6,
+            ),
+            address(
+                "56, Iris Street, Frenchs Forest, Northern Beaches Council, New South Wales, 2086, Australia",
+                -33.75,
+            ),
+            address("56 Iris Street, Beacon Hill NSW 2100", -33.74),
+        ]
+    }
+
+    #[test]
+    fn suburb_and_postcode_found() {
+        let scored = score_candidates("56 Iris Street", iris_streets(), &suburbs());
+        assert_eq!(scored[0].suburb.as_deref(), Some("FRENCHS FOREST"));
+        assert_eq!(scored[0].postcode, Some(2086));
+        assert_eq!(scored[0].score, 2);
+        assert_eq!(scored[1].suburb.as_deref(), Some("BEACON HILL"));
+        assert_eq!(scored[1].score, 2);
+        // not in the suburb table
+        assert_eq!(scored[2].suburb, None);
+        assert_eq!(scored[2].postcode, Some(2850));
+        assert_eq!(scored[2].score, 0);
+    }
+
+    #[test]
+    fn suburb_in_query_preferred() {
+        let scored = score_candidates("56 Iris St, Beacon Hill", iris_streets(), &suburbs());
+        assert_eq!(scored[0].suburb.as_deref(), Some("BEACON HILL"));
+        assert_eq!(scored[0].score, 4);
+        let scored = score_candidates("56 Iris St 2086", iris_streets(), &suburbs());
+        assert_eq!(scored[0].suburb.as_deref(), Some("FRENCHS FOREST"));
+    }
+
+    #[test]
+    fn same_name_different_postcode() {
+        let suburbs = vec![suburb("RICHMOND", 2753), suburb("RICHMOND", 2480)];
+        let found = find_suburb("1 Main St, Richmond NSW 2480", Some(2480), &suburbs).unwrap();
+        assert_eq!(found.postcode, 2480);
+    }
+
+    #[test]
+    fn best_picked() {
+        let found = resolve(
+            "56 Iris Street",
+            iris_streets(),
+            &suburbs(),
+            &Disambiguation::Best,
+        )
+        .unwrap();
+        assert_eq!(found.latitude_degree, -33.75);
+    }
+
+    #[test]
+    fn strict_fails_on_tie() {
+        let error = resolve(
+            "56 Iris Street",
+            iris_streets(),
+            &suburbs(),
+            &Disambiguation::Strict,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, HvError::AmbiguousAddress { candidates, .. } if candidates.len() == 3)
+        );
+        let found = resolve(
+            "56 Iris Street Beacon Hill",
+            iris_streets(),
+            &suburbs(),
+            &Disambiguation::Strict,
+        )
+        .unwrap();
+        assert_eq!(found.latitude_degree, -33.74);
+    }
+
+    #[test]
+    fn strict_same_place_not_ambiguous() {
+        let candidates = vec![
+            address("56, Iris Street, Frenchs Forest, 2086", -33.75),
+            address("House, 56, Iris Street, Frenchs Forest, 2086", -33.7501),
+        ];
+        assert!(resolve(
+            "56 Iris Street",
+            candidates,
+            &suburbs(),
+            &Disambiguation::Strict
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn pick() {
+        let pick = Disambiguation::Pick(Rc::new(|_, candidates: &[AddressCandidate]| {
+            candidates
+                .iter()
+                .position(|c| c.suburb.as_deref() == Some("BEACON HILL"))
+        }));
+        let found = resolve("56 Iris Street", iris_streets(), &suburbs(), &pick).unwrap();
+        assert_eq!(found.latitude_degree, -33.74);
+        let cancel = Disambiguation::Pick(Rc::new(|_, _: &[AddressCandidate]| None));
+        assert!(matches!(
+            resolve("56 Iris Street", iris_streets(), &suburbs(), &cancel),
+            Err(HvError::AmbiguousAddress { .. })
+        ));
+    }
+
+    #[test]
+    fn single_candidate_never_ambiguous() {
+        let strict = resolve(
+            "56 Iris Street",
+            vec![address("56 Iris Street, Mudgee", -32.6)],
+            &suburbs(),
+            &Disambiguation::Strict,
+        );
+        assert!(strict.is_ok());
+        assert!(matches!(
+            resolve("nowhere", vec![], &suburbs(), &Disambiguation::Strict),
+            Err(HvError::GeocodeNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,109 @@
+use std::path;
+
+use thiserror::Error;
+
+use crate::geometry::GeometryError;
+
+#[derive(Error, Debug)]
+pub enum HvError {
+    #[error("no result found for address '{0}'")]
+    GeocodeNotFound(String),
+
+    #[error("request to '{url}' failed: {source}")]
+    UpstreamHttp {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("failed to parse response from '{endpoint}': {reason}")]
+    MalformedResponse { endpoint: String, reason: String },
+
+    #[error("location ({latitude_degree}, {longitude_degree}) is outside of the area covered by the suburb map (NSW)")]
+    OutOfCoverage {
+        latitude_degree: f64,
+        longitude_degree: f64,
+    },
+
+    #[error("malformed suburb record '{code}': {reason}")]
+    MalformedSuburbRecord { code: String, reason: String },
+
+    #[error("only LineString is supported for lines, but got '{0}'")]
+    UnsupportedGeometry(String),
+
+    #[error("can not find voltage for id='{0}'")]
+    MissingVoltage(String),
+
+    #[error("only 1 voltage should be in the map, but got '{voltages:?}' for id='{line_id}'")]
+    MultipleVoltages {
+        line_id: String,
+        voltages: Vec<String>,
+    },
+
+    #[error("failed to parsed voltage string '{0}' to u16")]
+    InvalidVoltage(String),
+
+    #[error("unsupported batch file '{}', only .csv and .jsonl are supported", .0.display())]
+    UnsupportedBatchFormat(path::PathBuf),
+
+    #[error("malformed batch record at row {row}: {reason}")]
+    MalformedBatchRecord { row: usize, reason: String },
+
+    #[error("failed to open batch file '{}': {source}", path.display())]
+    BatchIo {
+        path: path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to write batch output: {0}")]
+    BatchOutput(String),
+
+    #[error("failed to write report: {0}")]
+    ReportOutput(String),
+
+    #[error("failed to export vtk to '{}': {source}", path.display())]
+    VtkExport {
+        path: path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to export kml to '{}': {source}", path.display())]
+    KmlExport {
+        path: path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to load gazetteer '{}': {reason}", path.display())]
+    Gazetteer { path: path::PathBuf, reason: String },
+
+    #[error("more than one place found for address '{address}': {candidates:?}")]
+    AmbiguousAddress {
+        address: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("failed to load clearance policy '{}': {reason}", path.display())]
+    Policy { path: path::PathBuf, reason: String },
+
+    #[error("malformed lot: {0}")]
+    MalformedLot(String),
+
+    #[error("invalid geometry for id='{id}': {source}")]
+    InvalidGeometry {
+        id: String,
+        #[source]
+        source: GeometryError,
+    },
+
+    #[error("cache io error on '{}': {source}", path.display())]
+    CacheIo {
+        path: path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, HvError>;
@@ -55,6 +55,10 @@ impl Vector {
         }
     }
 
+    pub fn dot(&self, v: &Vector) -> f64 {
+        self.x * v.x + self.y * v.y
+    }
+
     pub fn cross(&self, v: &Vector) -> f64 {
         self.x * v.y - self.y * v.x
     }
@@ -112,6 +116,13 @@ mod test_vector {
         test_utils::assert_close_to(product, 34.0, TOL)
     }
 
+    #[test]
+    fn vector_dot_product() {
+        let v1 = Vector { x: 13.0, y: 8.0 };
+        let v2 = Vector { x: -1.0, y: 2.0 };
+        test_utils::assert_close_to(v1.dot(&v2), 3.0, TOL)
+    }
+
     #[test]
     fn vector_det() {
         let v = Vector { x: 3.0, y: 4.0 };
@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+use super::basic::Point;
+
+#[derive(Error, Debug, Clone)]
+pub enum GeometryError {
+    #[error("need at least {expected} points, got {got}")]
+    TooFewPoints { expected: usize, got: usize },
+
+    #[error("point {index} at {point} is the same as the one before it")]
+    DuplicatePoint { index: usize, point: Point },
+}
+
+pub type Result<T> = std::result::Result<T, GeometryError>;
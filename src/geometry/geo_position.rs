@@ -1,38 +1,57 @@
 use lazy_static::lazy_static;
 
 use crate::geometry::basic::Point;
+use crate::geometry::projection::Projection;
 
-const EARTH_RADIUS_KM: f64 = 6371.0710;
+pub(crate) const EARTH_RADIUS_KM: f64 = 6371.0710;
 
 lazy_static! {
-    static ref SYDNEY_CENTRAL_POSITION: GeoPosition = GeoPosition {
+    pub(crate) static ref SYDNEY_CENTRAL_POSITION: GeoPosition = GeoPosition {
         latitude_radius: -33.88243560003056_f64.to_radians(),
         longitude_radius: 151.2064118987779_f64.to_radians(),
     };
 }
+#[derive(Clone, Debug, PartialEq)]
 pub struct GeoPosition {
     pub latitude_radius: f64,
     pub longitude_radius: f64,
 }
 
 impl GeoPosition {
+    pub fn from_degree(latitude_degree: f64, longitude_degree: f64) -> Self {
+        GeoPosition {
+            latitude_radius: latitude_degree.to_radians(),
+            longitude_radius: longitude_degree.to_radians(),
+        }
+    }
+
+    // projected with the default projection, which is centred at sydney central.
     pub fn to_cartesian(&self) -> Point {
-        // x, longitude
-        let x = {
-            let radius = EARTH_RADIUS_KM * 1000.0 * self.latitude_radius.cos();
-            radius * (self.longitude_radius - SYDNEY_CENTRAL_POSITION.longitude_radius)
-        };
-        let y = EARTH_RADIUS_KM
-            * 1000.0
-            * (self.latitude_radius - SYDNEY_CENTRAL_POSITION.latitude_radius);
+        Projection::default().project(self)
+    }
 
-        Point { x, y }
+    pub fn from_cartesian(point: &Point) -> Self {
+        Projection::default().unproject(point)
+    }
+
+    pub fn latitude_degree(&self) -> f64 {
+        self.latitude_radius.to_degrees()
+    }
+
+    pub fn longitude_degree(&self) -> f64 {
+        self.longitude_radius.to_degrees()
+    }
+}
+
+impl Point {
+    pub fn to_geo_position(&self) -> GeoPosition {
+        GeoPosition::from_cartesian(self)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::geometry::basic::Point;
+    use crate::geometry::basic::{test_utils::assert_close_to, Point};
 
     use super::GeoPosition;
 
@@ -73,6 +92,16 @@ mod test {
         )
     }
 
+    #[test]
+    fn cartesian_to_latitude_rosebery() {
+        let position = GeoPosition::from_cartesian(&Point {
+            x: -738.6767504988909,
+            y: -4301.452856541296,
+        });
+        assert_close_to(position.latitude_degree(), -33.921119441679096, 1e-5);
+        assert_close_to(position.longitude_degree(), 151.1984099658811, 1e-5);
+    }
+
     #[test]
     fn latitude_to_cartesian_beacon_mainly() {
         let random_place = GeoPosition {
@@ -90,4 +119,58 @@ mod test {
             1.0,
         )
     }
+
+    #[test]
+    fn from_degree() {
+        let position = GeoPosition::from_degree(-33.921119441679096, 151.1984099658811);
+        assert_close_to(
+            position.latitude_radius,
+            -33.921119441679096_f64.to_radians(),
+            1e-15,
+        );
+        assert_close_to(
+            position.longitude_radius,
+            151.1984099658811_f64.to_radians(),
+            1e-15,
+        );
+    }
+
+    #[test]
+    fn round_trip_geo_position_across_sydney_basin() {
+        // from Campbelltown/Penrith in the south west to Palm Beach in the north east.
+        for lat_idx in 0..=20 {
+            for lon_idx in 0..=20 {
+                let latitude = -34.2 + 0.7 * (lat_idx as f64) / 20.0;
+                let longitude = 150.5 + 0.9 * (lon_idx as f64) / 20.0;
+                let position = GeoPosition::from_degree(latitude, longitude)
+                    .to_cartesian()
+                    .to_geo_position();
+                assert_close_to(position.latitude_degree(), latitude, 1e-10);
+                assert_close_to(position.longitude_degree(), longitude, 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_cartesian_across_sydney_basin() {
+        for x_idx in -10..=10 {
+            for y_idx in -10..=10 {
+                let point = Point {
+                    x: 4_000.0 * x_idx as f64,
+                    y: 4_000.0 * y_idx as f64,
+                };
+                point
+                    .to_geo_position()
+                    .to_cartesian()
+                    .assert_close_to(&point, 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn origin_to_sydney_central() {
+        let position = Point { x: 0.0, y: 0.0 }.to_geo_position();
+        assert_close_to(position.latitude_degree(), -33.88243560003056, 1e-12);
+        assert_close_to(position.longitude_degree(), 151.2064118987779, 1e-12);
+    }
 }
@@ -0,0 +1,313 @@
+use super::geo_position::{GeoPosition, EARTH_RADIUS_KM};
+
+// WGS84
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const WGS84_B: f64 = WGS84_A * (1.0 - WGS84_F);
+
+const VINCENTY_MAX_ITERATIONS: usize = 200;
+const VINCENTY_TOL: f64 = 1e-12;
+// golden section search on a span stops once the bracket is shorter than this, in meter.
+const SEGMENT_SEARCH_TOL_M: f64 = 1e-4;
+
+#[derive(Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum DistanceModel {
+    // on the projected plane
+    Planar,
+    // on a sphere with the mean earth radius
+    Spherical,
+    // on the WGS84 ellipsoid
+    Ellipsoidal,
+}
+
+pub fn haversine_distance(a: &GeoPosition, b: &GeoPosition) -> f64 {
+    EARTH_RADIUS_KM * 1000.0 * central_angle(a, b)
+}
+
+fn central_angle(a: &GeoPosition, b: &GeoPosition) -> f64 {
+    let d_lat = b.latitude_radius - a.latitude_radius;
+    let d_lon = b.longitude_radius - a.longitude_radius;
+    let h = (d_lat / 2.0).sin().powi(2)
+        + a.latitude_radius.cos() * b.latitude_radius.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * h.sqrt().min(1.0).asin()
+}
+
+fn initial_bearing(a: &GeoPosition, b: &GeoPosition) -> f64 {
+    let d_lon = b.longitude_radius - a.longitude_radius;
+    let y = d_lon.sin() * b.latitude_radius.cos();
+    let x = a.latitude_radius.cos() * b.latitude_radius.sin()
+        - a.latitude_radius.sin() * b.latitude_radius.cos() * d_lon.cos();
+    y.atan2(x)
+}
+
+// Vincenty's inverse formula. It falls back to haversine for nearly antipodal points where the
+// iteration does not converge, which never happens at the scale of a suburb.
+pub fn vincenty_distance(a: &GeoPosition, b: &GeoPosition) -> f64 {
+    let l = b.longitude_radius - a.longitude_radius;
+    let u1 = ((1.0 - WGS84_F) * a.latitude_radius.tan()).atan();
+    let u2 = ((1.0 - WGS84_F) * b.latitude_radius.tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..VINCENTY_MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+        if sin_sigma == 0.0 {
+            // same position
+            return 0.0;
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+        let cos_2_sigma_m = if cos_sq_alpha == 0.0 {
+            // both points on the equator
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha
+        };
+        let c = WGS84_F / 16.0 * cos_sq_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos_sq_alpha));
+        let lambda_prev = lambda;
+        lambda = l
+            + (1.0 - c)
+                * WGS84_F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2_sigma_m
+                            + c * cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m * cos_2_sigma_m)));
+        if (lambda - lambda_prev).abs() < VINCENTY_TOL {
+            let u_sq = cos_sq_alpha * (WGS84_A * WGS84_A - WGS84_B * WGS84_B) / (WGS84_B * WGS84_B);
+            let big_a =
+                1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = big_b
+                * sin_sigma
+                * (cos_2_sigma_m
+                    + big_b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m * cos_2_sigma_m)
+                            - big_b / 6.0
+                                * cos_2_sigma_m
+                                * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                                * (-3.0 + 4.0 * cos_2_sigma_m * cos_2_sigma_m)));
+            return WGS84_B * big_a * (sigma - delta_sigma);
+        }
+    }
+    warn!("vincenty formula does not converge, fallback to haversine");
+    haversine_distance(a, b)
+}
+
+// Cross track distance to the great circle arc from a to b, clamped to the end points.
+pub fn spherical_distance_to_segment(p: &GeoPosition, a: &GeoPosition, b: &GeoPosition) -> f64 {
+    let radius = EARTH_RADIUS_KM * 1000.0;
+    let angle_ap = central_angle(a, p);
+    let angle_ab = central_angle(a, b);
+    if angle_ab == 0.0 {
+        return radius * angle_ap;
+    }
+    let bearing_diff = initial_bearing(a, p) - initial_bearing(a, b);
+    let cross_track = (angle_ap.sin() * bearing_diff.sin()).asin();
+    // the projection falls behind a.
+    if bearing_diff.cos() < 0.0 {
+        return radius * angle_ap;
+    }
+    let along_track = (angle_ap.cos() / cross_track.cos()).clamp(-1.0, 1.0).acos();
+    if along_track > angle_ab {
+        return radius * central_angle(b, p);
+    }
+    radius * cross_track.abs()
+}
+
+// position at fraction t of the great circle arc from a to b
+fn interpolate(a: &GeoPosition, b: &GeoPosition, t: f64) -> GeoPosition {
+    let to_vector = |p: &GeoPosition| {
+        [
+            p.latitude_radius.cos() * p.longitude_radius.cos(),
+            p.latitude_radius.cos() * p.longitude_radius.sin(),
+            p.latitude_radius.sin(),
+        ]
+    };
+    let angle = central_angle(a, b);
+    if angle == 0.0 {
+        return a.clone();
+    }
+    let (va, vb) = (to_vector(a), to_vector(b));
+    let wa = ((1.0 - t) * angle).sin() / angle.sin();
+    let wb = (t * angle).sin() / angle.sin();
+    let v = [
+        wa * va[0] + wb * vb[0],
+        wa * va[1] + wb * vb[1],
+        wa * va[2] + wb * vb[2],
+    ];
+    GeoPosition {
+        latitude_radius: v[2].atan2((v[0] * v[0] + v[1] * v[1]).sqrt()),
+        longitude_radius: v[1].atan2(v[0]),
+    }
+}
+
+// Distance on the WGS84 ellipsoid to a span. A span is at most a few hundred meters, where the
+// geodesic and the great circle between its end points are indistinguishable, so the closest
+// point is searched along the great circle and measured with vincenty.
+pub fn ellipsoidal_distance_to_segment(p: &GeoPosition, a: &GeoPosition, b: &GeoPosition) -> f64 {
+    const INV_PHI: f64 = 0.618_033_988_749_895;
+    let distance_at = |t: f64| vincenty_distance(p, &interpolate(a, b, t));
+    let span = haversine_distance(a, b);
+    let (mut lo, mut hi) = (0.0_f64, 1.0_f64);
+    let mut t1 = hi - INV_PHI * (hi - lo);
+    let mut t2 = lo + INV_PHI * (hi - lo);
+    let (mut d1, mut d2) = (distance_at(t1), distance_at(t2));
+    while (hi - lo) * span > SEGMENT_SEARCH_TOL_M {
+        if d1 < d2 {
+            hi = t2;
+            t2 = t1;
+            d2 = d1;
+            t1 = hi - INV_PHI * (hi - lo);
+            d1 = distance_at(t1);
+        } else {
+            lo = t1;
+            t1 = t2;
+            d1 = d2;
+            t2 = lo + INV_PHI * (hi - lo);
+            d2 = distance_at(t2);
+        }
+    }
+    // end points are checked explicitly since the search never evaluates them.
+    d1.min(d2).min(distance_at(0.0)).min(distance_at(1.0))
+}
+
+pub fn distance_to_polyline(
+    p: &GeoPosition,
+    positions: &[GeoPosition],
+    model: &DistanceModel,
+) -> f64 {
+    positions
+        .windows(2)
+        .map(|w| match model {
+            DistanceModel::Ellipsoidal => ellipsoidal_distance_to_segment(p, &w[0], &w[1]),
+            // planar distance is calculated on the projected polyline, spherical is used
+            // here if it's ever asked for the geo positions.
+            _ => spherical_distance_to_segment(p, &w[0], &w[1]),
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::basic::test_utils::assert_close_to;
+
+    fn dms(degree: f64, minute: f64, second: f64) -> f64 {
+        degree.signum() * (degree.abs() + minute / 60.0 + second / 3600.0)
+    }
+
+    #[test]
+    fn vincenty_flinders_peak_to_buninyong() {
+        // the worked example from Vincenty's paper via Geoscience Australia
+        let flinders_peak =
+            GeoPosition::from_degree(dms(-37.0, 57.0, 3.72030), dms(144.0, 25.0, 29.52440));
+        let buninyong =
+            GeoPosition::from_degree(dms(-37.0, 39.0, 10.15610), dms(143.0, 55.0, 35.38390));
+        assert_close_to(
+            vincenty_distance(&flinders_peak, &buninyong),
+            54972.271,
+            1e-3,
+        );
+    }
+
+    #[test]
+    fn vincenty_same_position() {
+        let p = GeoPosition::from_degree(-33.8688, 151.2093);
+        assert_close_to(vincenty_distance(&p, &p), 0.0, 1e-9);
+    }
+
+    #[test]
+    fn haversine_one_degree_of_latitude() {
+        let a = GeoPosition::from_degree(-33.0, 151.0);
+        let b = GeoPosition::from_degree(-34.0, 151.0);
+        assert_close_to(
+            haversine_distance(&a, &b),
+            EARTH_RADIUS_KM * 1000.0 * 1.0_f64.to_radians(),
+            1e-6,
+        );
+    }
+
+    fn brute_force(
+        p: &GeoPosition,
+        a: &GeoPosition,
+        b: &GeoPosition,
+        f: fn(&GeoPosition, &GeoPosition) -> f64,
+    ) -> f64 {
+        (0..=10_000)
+            .map(|i| f(p, &interpolate(a, b, i as f64 / 10_000.0)))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    // a 330kV span going north west from Rosebery, roughly 1km long
+    fn span() -> (GeoPosition, GeoPosition) {
+        (
+            GeoPosition::from_degree(-33.921119, 151.198410),
+            GeoPosition::from_degree(-33.914, 151.191),
+        )
+    }
+
+    #[test]
+    fn spherical_within_span() {
+        let (a, b) = span();
+        let p = GeoPosition::from_degree(-33.915, 151.198);
+        assert_close_to(
+            spherical_distance_to_segment(&p, &a, &b),
+            brute_force(&p, &a, &b, haversine_distance),
+            1e-2,
+        );
+    }
+
+    #[test]
+    fn spherical_clamped_to_end_points() {
+        let (a, b) = span();
+        let behind_a = GeoPosition::from_degree(-33.93, 151.21);
+        assert_close_to(
+            spherical_distance_to_segment(&behind_a, &a, &b),
+            haversine_distance(&behind_a, &a),
+            1e-6,
+        );
+        let beyond_b = GeoPosition::from_degree(-33.90, 151.18);
+        assert_close_to(
+            spherical_distance_to_segment(&beyond_b, &a, &b),
+            haversine_distance(&beyond_b, &b),
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn ellipsoidal_within_span() {
+        let (a, b) = span();
+        let p = GeoPosition::from_degree(-33.915, 151.198);
+        assert_close_to(
+            ellipsoidal_distance_to_segment(&p, &a, &b),
+            brute_force(&p, &a, &b, vincenty_distance),
+            1e-2,
+        );
+    }
+
+    #[test]
+    fn three_models_agree_around_sydney() {
+        use crate::geometry::{PolyLine, Projection, ProjectionMethod};
+
+        let (a, b) = span();
+        let p = GeoPosition::from_degree(-33.915, 151.198);
+        let projection = Projection::new(p.clone(), ProjectionMethod::Sinusoidal);
+        let planar = PolyLine::new(vec![projection.project(&a), projection.project(&b)])
+            .unwrap()
+            .distance_to(&projection.project(&p));
+        let spherical =
+            distance_to_polyline(&p, &[a.clone(), b.clone()], &DistanceModel::Spherical);
+        let ellipsoidal = distance_to_polyline(&p, &[a, b], &DistanceModel::Ellipsoidal);
+        // the sphere and the ellipsoid differ by less than 0.5% in Sydney
+        assert!((planar - spherical).abs() < 0.1, "{planar} vs {spherical}");
+        assert!((ellipsoidal - spherical).abs() / spherical < 5e-3);
+        assert!((ellipsoidal - spherical).abs() > 1e-3);
+    }
+}
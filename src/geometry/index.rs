@@ -0,0 +1,232 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{basic::Point, line::LineSegment};
+
+// A uniform grid over line segments. Every segment is registered in each cell its bounding box
+// overlaps, which is cheap as long as the cells are not much smaller than the segments.
+pub struct SegmentIndex<T> {
+    cell_size: f64,
+    entries: Vec<(LineSegment, T)>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    // (min x, min y, max x, max y) of the occupied cells
+    bounds: Option<(i64, i64, i64, i64)>,
+}
+
+impl<T> SegmentIndex<T> {
+    pub fn new(cell_size: f64) -> Self {
+        if cell_size <= 0.0 || !cell_size.is_finite() {
+            panic!("cell size must be positive, got '{}'", cell_size)
+        }
+        SegmentIndex {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn cell_of(&self, x: f64, y: f64) -> (i64, i64) {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+        )
+    }
+
+    pub fn insert(&mut self, segment: LineSegment, value: T) {
+        let idx = self.entries.len();
+        let (min_x, min_y) =
+            self.cell_of(segment.a.x.min(segment.b.x), segment.a.y.min(segment.b.y));
+        let (max_x, max_y) =
+            self.cell_of(segment.a.x.max(segment.b.x), segment.a.y.max(segment.b.y));
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(idx);
+            }
+        }
+        self.bounds = Some(match self.bounds {
+            Some((x0, y0, x1, y1)) => (x0.min(min_x), y0.min(min_y), x1.max(max_x), y1.max(max_y)),
+            None => (min_x, min_y, max_x, max_y),
+        });
+        self.entries.push((segment, value));
+    }
+
+    // occupied cells at chebyshev distance `ring` from `center`
+    fn ring_cells(&self, center: (i64, i64), ring: i64) -> Vec<(i64, i64)> {
+        let Some((x0, y0, x1, y1)) = self.bounds else {
+            return Vec::new();
+        };
+        let (cx, cy) = center;
+        let mut cells = Vec::new();
+        for y in [cy - ring, cy + ring] {
+            if (y0..=y1).contains(&y) {
+                cells.extend(((cx - ring).max(x0)..=(cx + ring).min(x1)).map(|x| (x, y)));
+            }
+            if ring == 0 {
+                return cells;
+            }
+        }
+        for x in [cx - ring, cx + ring] {
+            if (x0..=x1).contains(&x) {
+                cells.extend(((cy - ring + 1).max(y0)..=(cy + ring - 1).min(y1)).map(|y| (x, y)));
+            }
+        }
+        cells
+    }
+
+    // The nearest segment and the distance to it. Cells are searched ring by ring around the
+    // point, until every segment not searched yet is provably further than the best one.
+    pub fn nearest(&self, point: &Point) -> Option<(&LineSegment, &T, f64)> {
+        let (x0, y0, x1, y1) = self.bounds?;
+        let center = self.cell_of(point.x, point.y);
+        // rings closer than the occupied cells are empty
+        let first_ring = (x0 - center.0)
+            .max(center.0 - x1)
+            .max(y0 - center.1)
+            .max(center.1 - y1)
+            .max(0);
+        let last_ring = (center.0 - x0)
+            .max(x1 - center.0)
+            .max(center.1 - y0)
+            .max(y1 - center.1);
+        let mut visited = HashSet::<usize>::new();
+        let mut best: Option<(usize, f64)> = None;
+        for ring in first_ring..=last_ring {
+            for cell in self.ring_cells(center, ring) {
+                for idx in self.cells.get(&cell).into_iter().flatten() {
+                    if !visited.insert(*idx) {
+                        continue;
+                    }
+                    let distance = self.entries[*idx].0.distance_to_point(point);
+                    if best.is_none_or(|(_, d)| distance < d) {
+                        best = Some((*idx, distance));
+                    }
+                }
+            }
+            // anything outside this ring is at least `ring` cells away from the point
+            if best.is_some_and(|(_, d)| d <= ring as f64 * self.cell_size) {
+                break;
+            }
+        }
+        best.map(|(idx, distance)| (&self.entries[idx].0, &self.entries[idx].1, distance))
+    }
+
+    // Every segment within `radius` of the point, nearest first.
+    pub fn within(&self, point: &Point, radius: f64) -> Vec<(&LineSegment, &T, f64)> {
+        let (min_x, min_y) = self.cell_of(point.x - radius, point.y - radius);
+        let (max_x, max_y) = self.cell_of(point.x + radius, point.y + radius);
+        let mut visited = HashSet::<usize>::new();
+        let mut found = Vec::new();
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                for idx in self.cells.get(&(x, y)).into_iter().flatten() {
+                    if !visited.insert(*idx) {
+                        continue;
+                    }
+                    let (segment, value) = &self.entries[*idx];
+                    let distance = segment.distance_to_point(point);
+                    if distance <= radius {
+                        found.push((segment, value, distance));
+                    }
+                }
+            }
+        }
+        found.sort_by(|a, b| a.2.total_cmp(&b.2));
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::basic::test_utils::assert_close_to;
+
+    fn segment(ax: f64, ay: f64, bx: f64, by: f64) -> LineSegment {
+        LineSegment::new(Point { x: ax, y: ay }, Point { x: bx, y: by })
+    }
+
+    // a fan of segments spread over a few hundred cells
+    fn index() -> SegmentIndex<usize> {
+        let mut index = SegmentIndex::new(10.0);
+        for i in 0..50 {
+            let offset = i as f64 * 7.3;
+            index.insert(
+                segment(offset, -offset, offset + 25.0, 3.0 * offset - 40.0),
+                i,
+            );
+        }
+        index
+    }
+
+    fn brute_force(index: &SegmentIndex<usize>, point: &Point) -> (usize, f64) {
+        index
+            .entries
+            .iter()
+            .map(|(s, v)| (*v, s.distance_to_point(point)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+    }
+
+    #[test]
+    fn nearest_same_as_brute_force() {
+        let index = index();
+        for point in [
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 120.0, y: 33.0 },
+            Point {
+                x: -500.0,
+                y: 800.0,
+            },
+            Point { x: 1e4, y: -1e4 },
+            Point {
+                x: 200.0,
+                y: -150.0,
+            },
+        ] {
+            let (_, value, distance) = index.nearest(&point).unwrap();
+            let (expected_value, expected_distance) = brute_force(&index, &point);
+            assert_eq!(*value, expected_value);
+            assert_close_to(distance, expected_distance, 1e-10);
+        }
+    }
+
+    #[test]
+    fn nearest_on_empty_index() {
+        let index = SegmentIndex::<usize>::new(10.0);
+        assert!(index.nearest(&Point { x: 0.0, y: 0.0 }).is_none());
+    }
+
+    #[test]
+    fn within_radius() {
+        let index = index();
+        let point = Point { x: 120.0, y: 33.0 };
+        let found = index.within(&point, 30.0);
+        let expected = index
+            .entries
+            .iter()
+            .filter(|(s, _)| s.distance_to_point(&point) <= 30.0)
+            .count();
+        assert!(expected > 1);
+        assert_eq!(found.len(), expected);
+        assert!(found.windows(2).all(|w| w[0].2 <= w[1].2));
+        assert_eq!(*found[0].1, brute_force(&index, &point).0);
+    }
+
+    #[test]
+    fn segment_longer_than_cell() {
+        let mut index = SegmentIndex::new(1.0);
+        index.insert(segment(0.0, 0.0, 100.0, 0.0), "long");
+        index.insert(segment(0.0, 10.0, 1.0, 10.0), "short");
+        let (_, value, distance) = index.nearest(&Point { x: 50.0, y: 2.0 }).unwrap();
+        assert_eq!(*value, "long");
+        assert_close_to(distance, 2.0, 1e-10);
+        assert_eq!(index.within(&Point { x: 50.0, y: 2.0 }, 9.0).len(), 1);
+    }
+}
@@ -2,6 +2,54 @@ use std::fmt::Display;
 
 use super::basic::{Point, Vector, TOL};
 
+const COLLINEAR_TOL: f64 = 1e-9;
+
+// The closest point on a line to a given point.
+#[derive(Clone, Debug)]
+pub struct ClosestPoint {
+    pub distance: f64,
+    pub point: Point,
+    // index of the segment where the closest point is, always 0 for a single segment.
+    pub segment_index: usize,
+    // position of the closest point within the segment, 0 at the start and 1 at the end.
+    pub segment_t: f64,
+    // length along the line from its first point to the closest point.
+    pub distance_along: f64,
+}
+
+// Which way a -> b -> c turns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+    CounterClockwise,
+    Clockwise,
+    Collinear,
+}
+
+// Collinear when the sine of the angle between a -> b and a -> c is below `COLLINEAR_TOL`, so that
+// the answer doesn't depend on the scale, and projected coordinates far from the origin still
+// have enough digits left in their differences.
+pub fn orientation(a: &Point, b: &Point, c: &Point) -> Orientation {
+    let vec_ab = Vector::from_points(a, b);
+    let vec_ac = Vector::from_points(a, c);
+    let cross = vec_ab.cross(&vec_ac);
+    if cross.abs() <= COLLINEAR_TOL * vec_ab.det() * vec_ac.det() {
+        Orientation::Collinear
+    } else if cross > 0.0 {
+        Orientation::CounterClockwise
+    } else {
+        Orientation::Clockwise
+    }
+}
+
+// Where two segments meet.
+#[derive(Clone, Debug)]
+pub enum SegmentIntersection {
+    Point(Point),
+    // the segments are collinear and share this part
+    Overlap(LineSegment),
+}
+
+#[derive(Clone, Debug)]
 pub struct LineSegment {
     pub a: Point,
     pub b: Point,
@@ -21,57 +69,21 @@ impl LineSegment {
         LineSegment { a, b }
     }
 
-    fn find_projection(&self, point: &Point) -> Point {
-        let dy = self.b.y - self.a.y;
-        let dx = self.b.x - self.a.x;
-        // slop is infinite. e.g. x=c
-        if f64::abs(dx) < TOL {
-            return Point {
-                x: self.a.x,
-                y: point.y,
-            };
-        }
-        // y = kx + b
-        let k = dy / dx;
-        let b = self.a.y - k * self.a.x;
-        let p_x = (point.x + k * point.y - k * b) / (1.0 + k * k);
-        let p_y = (k * point.x + k * k * point.y + b) / (1.0 + k * k);
-        Point { x: p_x, y: p_y }
-    }
-
-    fn find_closest_point(&self, point: &Point) -> Point {
-        let projection = self.find_projection(point);
-        let vec_point_to_projection = Vector::from_points(point, &projection);
-        if vec_point_to_projection.det() < TOL {
-            // point is on the line, cross product will be zero.
-            return projection;
-        }
-        let vec_projection_to_a = Vector::from_points(&projection, &self.a);
-        if vec_projection_to_a.det() < TOL {
-            // projection is the end point, cross product will be zero.
-            return self.a.clone();
-        }
-        let vec_projection_to_b = Vector::from_points(&projection, &self.b);
-        if vec_projection_to_b.det() < TOL {
-            // projection is the end point, cross product will be zero.
-            return self.b.clone();
-        }
-        let cross_a = vec_point_to_projection.cross(&vec_projection_to_a);
-        let cross_b = vec_point_to_projection.cross(&vec_projection_to_b);
-
-        if (cross_a > 0.0 && cross_b > 0.0) || (cross_a < 0.0 && cross_b < 0.0) {
-            // same sign, meaning the projection is not within the segment
-            // closest point will be either a or b, whichever is closer.
-            let p_to_a = point.distance_to(&self.a);
-            let p_to_b = point.distance_to(&self.b);
-            if p_to_a > p_to_b {
-                self.b.clone()
-            } else {
-                self.a.clone()
-            }
+    // The projection is a + t * ab with t = ap . ab / ab . ab, which stays accurate for vertical and
+    // near vertical segments where a slope would blow up.
+    pub fn find_projection(&self, point: &Point) -> Point {
+        self.point_at(self.parameter_of(point))
+    }
+
+    // the projection clamped to the segment, the end points are returned as they are
+    pub fn find_closest_point(&self, point: &Point) -> Point {
+        let t = self.parameter_of(point);
+        if t <= 0.0 {
+            self.a.clone()
+        } else if t >= 1.0 {
+            self.b.clone()
         } else {
-            // different sign, meaning the projection is not within the segment
-            projection
+            self.point_at(t)
         }
     }
 
@@ -79,6 +91,159 @@ impl LineSegment {
         let closest_point = self.find_closest_point(p);
         closest_point.distance_to(p)
     }
+
+    pub fn length(&self) -> f64 {
+        self.a.distance_to(&self.b)
+    }
+
+    // Length of the part of the segment inside the circle, the points where the segment crosses
+    // the circle are found by solving |a + t * ab - center| = radius for t.
+    pub fn length_within(&self, center: &Point, radius: f64) -> f64 {
+        let vec_ab = Vector::from_points(&self.a, &self.b);
+        let vec_center_to_a = Vector::from_points(center, &self.a);
+        let qa = vec_ab.dot(&vec_ab);
+        let qb = 2.0 * vec_center_to_a.dot(&vec_ab);
+        let qc = vec_center_to_a.dot(&vec_center_to_a) - radius * radius;
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let t0 = ((-qb - discriminant.sqrt()) / (2.0 * qa)).max(0.0);
+        let t1 = ((-qb + discriminant.sqrt()) / (2.0 * qa)).min(1.0);
+        (t1 - t0).max(0.0) * self.length()
+    }
+
+    // shorter than the tolerance, e.g. a segment not made with `new`
+    fn is_degenerate(&self) -> bool {
+        self.a.distance_to(&self.b) < TOL
+    }
+
+    fn point_at(&self, t: f64) -> Point {
+        Point {
+            x: self.a.x + t * (self.b.x - self.a.x),
+            y: self.a.y + t * (self.b.y - self.a.y),
+        }
+    }
+
+    // where the point is along the line through the segment, 0 at a and 1 at b, always 0 for a
+    // degenerate segment
+    fn parameter_of(&self, point: &Point) -> f64 {
+        if self.is_degenerate() {
+            return 0.0;
+        }
+        let vec_ab = Vector::from_points(&self.a, &self.b);
+        Vector::from_points(&self.a, point).dot(&vec_ab) / vec_ab.dot(&vec_ab)
+    }
+
+    // the point is on the segment, ends included
+    pub fn contains_point(&self, point: &Point) -> bool {
+        if self.is_degenerate() {
+            return self.a.distance_to(point) < TOL;
+        }
+        orientation(&self.a, &self.b, point) == Orientation::Collinear
+            && (0.0..=1.0).contains(&self.parameter_of(point))
+    }
+
+    // The ends of each segment are on different sides of the other one when they cross. A collinear
+    // end is where they touch, as long as it's on the other segment.
+    pub fn intersection(&self, other: &LineSegment) -> Option<SegmentIntersection> {
+        if self.is_degenerate() {
+            return other
+                .contains_point(&self.a)
+                .then(|| SegmentIntersection::Point(self.a.clone()));
+        }
+        if other.is_degenerate() {
+            return self
+                .contains_point(&other.a)
+                .then(|| SegmentIntersection::Point(other.a.clone()));
+        }
+        let side_c = orientation(&self.a, &self.b, &other.a);
+        let side_d = orientation(&self.a, &self.b, &other.b);
+        if side_c == Orientation::Collinear && side_d == Orientation::Collinear {
+            return self.collinear_overlap(other);
+        }
+        let side_a = orientation(&other.a, &other.b, &self.a);
+        let side_b = orientation(&other.a, &other.b, &self.b);
+        if side_c == side_d || side_a == side_b {
+            return None;
+        }
+        let touching = [
+            (side_c, &other.a),
+            (side_d, &other.b),
+            (side_a, &self.a),
+            (side_b, &self.b),
+        ]
+        .into_iter()
+        .find(|(side, _)| *side == Orientation::Collinear);
+        if let Some((_, end)) = touching {
+            return Some(SegmentIntersection::Point(end.clone()));
+        }
+        // by the signed distances of a and b to the other segment
+        let vec_cd = Vector::from_points(&other.a, &other.b);
+        let cross_a = vec_cd.cross(&Vector::from_points(&other.a, &self.a));
+        let cross_b = vec_cd.cross(&Vector::from_points(&other.a, &self.b));
+        let t = (cross_a / (cross_a - cross_b)).clamp(0.0, 1.0);
+        Some(SegmentIntersection::Point(self.point_at(t)))
+    }
+
+    fn collinear_overlap(&self, other: &LineSegment) -> Option<SegmentIntersection> {
+        let t_c = self.parameter_of(&other.a);
+        let t_d = self.parameter_of(&other.b);
+        let t_start = t_c.min(t_d).max(0.0);
+        let t_end = t_c.max(t_d).min(1.0);
+        if t_start > t_end {
+            return None;
+        }
+        let (start, end) = (self.point_at(t_start), self.point_at(t_end));
+        if start.distance_to(&end) < TOL {
+            Some(SegmentIntersection::Point(start))
+        } else {
+            Some(SegmentIntersection::Overlap(LineSegment {
+                a: start,
+                b: end,
+            }))
+        }
+    }
+
+    pub fn intersects(&self, other: &LineSegment) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    // The closest points between two segments, on this one and then on the other. Segments that
+    // meet are 0 apart where they meet, otherwise one of the closest points is an end point.
+    pub fn closest_points_to_segment(&self, other: &LineSegment) -> (Point, Point) {
+        match self.intersection(other) {
+            Some(SegmentIntersection::Point(p)) => return (p.clone(), p),
+            Some(SegmentIntersection::Overlap(overlap)) => return (overlap.a.clone(), overlap.a),
+            None => {}
+        }
+        [
+            (self.a.clone(), other.find_closest_point(&self.a)),
+            (self.b.clone(), other.find_closest_point(&self.b)),
+            (self.find_closest_point(&other.a), other.a.clone()),
+            (self.find_closest_point(&other.b), other.b.clone()),
+        ]
+        .into_iter()
+        .min_by(|(p0, q0), (p1, q1)| p0.distance_to(q0).total_cmp(&p1.distance_to(q1)))
+        .expect("always 4 pairs")
+    }
+
+    pub fn distance_to_segment(&self, other: &LineSegment) -> f64 {
+        let (p, q) = self.closest_points_to_segment(other);
+        p.distance_to(&q)
+    }
+
+    pub fn closest_point_to(&self, p: &Point) -> ClosestPoint {
+        let closest_point = self.find_closest_point(p);
+        let segment_t = self.parameter_of(p).clamp(0.0, 1.0);
+        ClosestPoint {
+            distance: closest_point.distance_to(p),
+            point: closest_point,
+            segment_index: 0,
+            segment_t,
+            distance_along: segment_t * self.length(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +261,7 @@ impl LineSegment {
 #[cfg(test)]
 mod test_line_segment {
     use super::*;
+    use crate::geometry::basic::test_utils::assert_close_to;
 
     #[test]
     fn find_projection_point_outside() {
@@ -152,4 +318,337 @@ mod test_line_segment {
         let closest_point = line_segment.find_closest_point(&Point { x: -100.0, y: 1.0 });
         closest_point.assert_close_to(&Point { x: 0.0, y: 0.0 }, TOL)
     }
+
+    #[test]
+    fn closest_point_to_within_segment() {
+        let line_segment = LineSegment::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 });
+        let closest = line_segment.closest_point_to(&Point { x: 1.0, y: 3.0 });
+        assert_close_to(closest.distance, 3.0, TOL);
+        closest
+            .point
+            .assert_close_to(&Point { x: 1.0, y: 0.0 }, TOL);
+        assert_eq!(closest.segment_index, 0);
+        assert_close_to(closest.segment_t, 0.25, TOL);
+        assert_close_to(closest.distance_along, 1.0, TOL);
+    }
+
+    #[test]
+    fn closest_point_to_beyond_end_point() {
+        let line_segment = LineSegment::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 });
+        let closest = line_segment.closest_point_to(&Point { x: 7.0, y: 4.0 });
+        assert_close_to(closest.distance, 5.0, TOL);
+        closest
+            .point
+            .assert_close_to(&Point { x: 4.0, y: 0.0 }, TOL);
+        assert_close_to(closest.segment_t, 1.0, TOL);
+        assert_close_to(closest.distance_along, 4.0, TOL);
+    }
+
+    #[test]
+    fn length_within_crossing_circle() {
+        let line_segment = LineSegment::new(Point { x: -10.0, y: 3.0 }, Point { x: 10.0, y: 3.0 });
+        let length = line_segment.length_within(&Point { x: 0.0, y: 0.0 }, 5.0);
+        assert_close_to(length, 8.0, 1e-10);
+    }
+
+    #[test]
+    fn length_within_from_inside() {
+        let line_segment = LineSegment::new(Point { x: 0.0, y: 3.0 }, Point { x: 10.0, y: 3.0 });
+        let length = line_segment.length_within(&Point { x: 0.0, y: 0.0 }, 5.0);
+        assert_close_to(length, 4.0, 1e-10);
+        let inside = LineSegment::new(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 });
+        assert_close_to(
+            inside.length_within(&Point { x: 0.0, y: 0.0 }, 5.0),
+            inside.length(),
+            1e-10,
+        );
+    }
+
+    #[test]
+    fn length_within_outside() {
+        let line_segment = LineSegment::new(Point { x: -10.0, y: 6.0 }, Point { x: 10.0, y: 6.0 });
+        assert_eq!(
+            line_segment.length_within(&Point { x: 0.0, y: 0.0 }, 5.0),
+            0.0
+        );
+        // the line through the segment crosses the circle, but not the segment itself
+        let line_segment = LineSegment::new(Point { x: 6.0, y: 0.0 }, Point { x: 10.0, y: 0.0 });
+        assert_eq!(
+            line_segment.length_within(&Point { x: 0.0, y: 0.0 }, 5.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn distance_to_segment_crossing() {
+        let ab = LineSegment::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 4.0 });
+        let cd = LineSegment::new(Point { x: 0.0, y: 4.0 }, Point { x: 4.0, y: 0.0 });
+        let (p, q) = ab.closest_points_to_segment(&cd);
+        p.assert_close_to(&Point { x: 2.0, y: 2.0 }, 1e-12);
+        q.assert_close_to(&Point { x: 2.0, y: 2.0 }, 1e-12);
+        assert_eq!(ab.distance_to_segment(&cd), 0.0);
+    }
+
+    #[test]
+    fn distance_to_segment_apart() {
+        let ab = LineSegment::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 });
+        // an end of the other segment is the closest
+        let cd = LineSegment::new(Point { x: 2.0, y: 3.0 }, Point { x: 5.0, y: 9.0 });
+        let (p, q) = ab.closest_points_to_segment(&cd);
+        p.assert_close_to(&Point { x: 2.0, y: 0.0 }, 1e-12);
+        q.assert_close_to(&Point { x: 2.0, y: 3.0 }, 1e-12);
+        // an end of this segment is the closest
+        let cd = LineSegment::new(Point { x: 6.0, y: -5.0 }, Point { x: 6.0, y: 5.0 });
+        assert_close_to(ab.distance_to_segment(&cd), 2.0, 1e-12);
+        assert_close_to(cd.distance_to_segment(&ab), 2.0, 1e-12);
+        // parallel
+        let cd = LineSegment::new(Point { x: 1.0, y: 1.0 }, Point { x: 3.0, y: 1.0 });
+        assert_close_to(ab.distance_to_segment(&cd), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn distance_to_segment_touching() {
+        let ab = LineSegment::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 });
+        let cd = LineSegment::new(Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 5.0 });
+        assert_close_to(ab.distance_to_segment(&cd), 0.0, 1e-12);
+    }
+
+    #[test]
+    fn find_closest_point_on_line_beyond_end_point() {
+        let line_segment = LineSegment::new(Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 2.0 });
+        line_segment
+            .find_closest_point(&Point { x: 2.0, y: 3.0 })
+            .assert_close_to(&Point { x: 2.0, y: 2.0 }, 1e-12);
+        line_segment
+            .find_closest_point(&Point { x: 2.0, y: -1.0 })
+            .assert_close_to(&Point { x: 2.0, y: 0.0 }, 1e-12);
+    }
+
+    fn segment(ax: f64, ay: f64, bx: f64, by: f64) -> LineSegment {
+        LineSegment::new(Point { x: ax, y: ay }, Point { x: bx, y: by })
+    }
+
+    fn intersection_point(ab: &LineSegment, cd: &LineSegment) -> Point {
+        match ab.intersection(cd) {
+            Some(SegmentIntersection::Point(p)) => p,
+            other => panic!("expect a point but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn orientation_of_points() {
+        let a = Point { x: 0.0, y: 0.0 };
+        let b = Point { x: 1.0, y: 0.0 };
+        assert_eq!(
+            orientation(&a, &b, &Point { x: 0.5, y: 1.0 }),
+            Orientation::CounterClockwise
+        );
+        assert_eq!(
+            orientation(&a, &b, &Point { x: 0.5, y: -1.0 }),
+            Orientation::Clockwise
+        );
+        assert_eq!(
+            orientation(&a, &b, &Point { x: 7.0, y: 0.0 }),
+            Orientation::Collinear
+        );
+        // the same at the scale of projected coordinates
+        let a = Point {
+            x: 300_000.0,
+            y: 6_250_000.0,
+        };
+        let b = Point {
+            x: 300_000.1,
+            y: 6_250_100.0,
+        };
+        let c = Point {
+            x: 300_000.2,
+            y: 6_250_200.0,
+        };
+        assert_eq!(orientation(&a, &b, &c), Orientation::Collinear);
+        let c = Point {
+            x: 300_000.21,
+            y: 6_250_200.0,
+        };
+        assert_eq!(orientation(&a, &b, &c), Orientation::Clockwise);
+    }
+
+    #[test]
+    fn intersection_crossing() {
+        let p = intersection_point(&segment(0.0, 0.0, 4.0, 4.0), &segment(0.0, 4.0, 4.0, 0.0));
+        p.assert_close_to(&Point { x: 2.0, y: 2.0 }, 1e-12);
+        // near vertical, where a slope is huge
+        let p = intersection_point(
+            &segment(1e5, -10.0, 1e5 + 1e-9, 10.0),
+            &segment(99_990.0, 0.0, 100_010.0, 0.0),
+        );
+        p.assert_close_to(&Point { x: 1e5, y: 0.0 }, 1e-9);
+        assert!(!segment(0.0, 0.0, 4.0, 4.0).intersects(&segment(0.0, 4.0, 1.0, 3.5)));
+    }
+
+    #[test]
+    fn intersection_touching() {
+        // an end on the other segment
+        let p = intersection_point(&segment(0.0, 0.0, 4.0, 0.0), &segment(2.0, 0.0, 2.0, 5.0));
+        p.assert_close_to(&Point { x: 2.0, y: 0.0 }, 1e-12);
+        let p = intersection_point(&segment(2.0, 5.0, 2.0, 0.0), &segment(0.0, 0.0, 4.0, 0.0));
+        p.assert_close_to(&Point { x: 2.0, y: 0.0 }, 1e-12);
+        // sharing an end
+        let p = intersection_point(&segment(0.0, 0.0, 4.0, 0.0), &segment(4.0, 0.0, 5.0, 3.0));
+        p.assert_close_to(&Point { x: 4.0, y: 0.0 }, 1e-12);
+        // the end is on the line through the other segment, but beyond it
+        assert!(!segment(0.0, 0.0, 4.0, 0.0).intersects(&segment(5.0, 0.0, 5.0, 3.0)));
+    }
+
+    #[test]
+    fn intersection_collinear() {
+        match segment(0.0, 0.0, 4.0, 0.0).intersection(&segment(6.0, 0.0, 2.0, 0.0)) {
+            Some(SegmentIntersection::Overlap(overlap)) => {
+                overlap.assert_close_to(&segment(2.0, 0.0, 4.0, 0.0), 1e-12)
+            }
+            other => panic!("expect an overlap but got {:?}", other),
+        }
+        // end to end
+        let p = intersection_point(&segment(0.0, 0.0, 4.0, 4.0), &segment(4.0, 4.0, 6.0, 6.0));
+        p.assert_close_to(&Point { x: 4.0, y: 4.0 }, 1e-12);
+        // on the same line, apart
+        assert!(!segment(0.0, 0.0, 4.0, 0.0).intersects(&segment(5.0, 0.0, 6.0, 0.0)));
+        // parallel
+        assert!(!segment(0.0, 0.0, 4.0, 0.0).intersects(&segment(0.0, 1.0, 4.0, 1.0)));
+    }
+
+    #[test]
+    fn intersection_degenerate() {
+        let dot = LineSegment {
+            a: Point { x: 2.0, y: 0.0 },
+            b: Point { x: 2.0, y: 0.0 },
+        };
+        let ab = segment(0.0, 0.0, 4.0, 0.0);
+        intersection_point(&dot, &ab).assert_close_to(&Point { x: 2.0, y: 0.0 }, 1e-12);
+        intersection_point(&ab, &dot).assert_close_to(&Point { x: 2.0, y: 0.0 }, 1e-12);
+        let away = segment(0.0, 3.0, 4.0, 3.0);
+        assert!(!dot.intersects(&away));
+        assert_close_to(dot.distance_to_segment(&away), 3.0, 1e-12);
+        assert_close_to(away.distance_to_segment(&dot), 3.0, 1e-12);
+    }
+
+    #[test]
+    fn distance_to_segment_collinear() {
+        let ab = segment(0.0, 0.0, 4.0, 0.0);
+        assert_close_to(
+            ab.distance_to_segment(&segment(6.0, 0.0, 9.0, 0.0)),
+            2.0,
+            1e-12,
+        );
+        assert_eq!(ab.distance_to_segment(&segment(3.0, 0.0, 9.0, 0.0)), 0.0);
+    }
+}
+
+#[cfg(test)]
+mod prop_test_line_segment {
+    use super::*;
+    use proptest::prelude::*;
+
+    const SAMPLE_SIZE: usize = 2000;
+
+    fn sample(segment: &LineSegment, size: usize) -> impl Iterator<Item = Point> + '_ {
+        (0..=size).map(move |idx| segment.point_at(idx as f64 / size as f64))
+    }
+
+    // segments of up to a few km, around the origin or as far from it as projected coordinates are
+    fn segment() -> impl Strategy<Value = LineSegment> {
+        (
+            prop_oneof![Just(0.0), -1e6..1e6f64],
+            prop_oneof![Just(0.0), -1e7..1e7f64],
+            -5e3..5e3f64,
+            -5e3..5e3f64,
+            -5e3..5e3f64,
+            -5e3..5e3f64,
+        )
+            .prop_filter("not degenerate", |(_, _, ax, ay, bx, by)| {
+                (bx - ax).hypot(by - ay) > 1e-3
+            })
+            .prop_map(|(x, y, ax, ay, bx, by)| {
+                LineSegment::new(
+                    Point {
+                        x: x + ax,
+                        y: y + ay,
+                    },
+                    Point {
+                        x: x + bx,
+                        y: y + by,
+                    },
+                )
+            })
+    }
+
+    // a point near the segment, within a few times its length
+    fn point_near(segment: &LineSegment) -> impl Strategy<Value = Point> {
+        let (x, y) = (segment.a.x, segment.a.y);
+        let reach = 3.0 * segment.length();
+        (-reach..reach, -reach..reach).prop_map(move |(dx, dy)| Point {
+            x: x + dx,
+            y: y + dy,
+        })
+    }
+
+    // a near vertical segment, the slope is up to 1e12
+    fn near_vertical() -> impl Strategy<Value = LineSegment> {
+        (-1e6..1e6f64, -1e7..1e7f64, -1e-9..1e-9f64, 1.0..5e3f64).prop_map(|(x, y, dx, dy)| {
+            LineSegment::new(
+                Point { x, y },
+                Point {
+                    x: x + dx,
+                    y: y + dy,
+                },
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn closest_point_matches_sampling(
+            (segment, point) in segment().prop_flat_map(|s| (Just(s.clone()), point_near(&s)))
+        ) {
+            let distance = segment.distance_to_point(&point);
+            let sampled = sample(&segment, SAMPLE_SIZE)
+                .map(|p| p.distance_to(&point))
+                .fold(f64::INFINITY, f64::min);
+            // rounding of coordinates around 1e7
+            let tol = 1e-8 * (1.0 + segment.a.x.abs() + segment.a.y.abs());
+            prop_assert!(distance <= sampled + tol, "{} > {}", distance, sampled);
+            // no sample is further than half a step from the closest point
+            prop_assert!(sampled <= distance + segment.length() / SAMPLE_SIZE as f64 + tol);
+        }
+
+        #[test]
+        fn closest_point_on_segment(
+            (segment, point) in segment().prop_flat_map(|s| (Just(s.clone()), point_near(&s)))
+        ) {
+            let closest = segment.closest_point_to(&point);
+            prop_assert!((0.0..=1.0).contains(&closest.segment_t));
+            let tol = 1e-8 * (1.0 + segment.a.x.abs() + segment.a.y.abs());
+            prop_assert!(closest.point.distance_to(&segment.point_at(closest.segment_t)) <= tol);
+            prop_assert!((closest.distance - point.distance_to(&closest.point)).abs() <= tol);
+        }
+
+        #[test]
+        fn projection_perpendicular_near_vertical(segment in near_vertical(), dx in -1e3..1e3f64, t in -1.0..2.0f64) {
+            let on_line = segment.point_at(t);
+            let point = Point { x: on_line.x + dx, y: on_line.y };
+            let projection = segment.find_projection(&point);
+            // a point moved sideways projects back to where it was, whatever the slope
+            prop_assert!(projection.distance_to(&on_line) <= 1e-6);
+        }
+
+        #[test]
+        fn distance_to_segment_matches_sampling(this in segment(), other in segment()) {
+            let distance = this.distance_to_segment(&other);
+            let sampled = sample(&other, 200)
+                .map(|p| this.distance_to_point(&p))
+                .fold(f64::INFINITY, f64::min);
+            let tol = 1e-8 * (1.0 + this.a.x.abs() + this.a.y.abs() + other.a.x.abs() + other.a.y.abs());
+            prop_assert!(distance <= sampled + tol, "{} > {}", distance, sampled);
+            prop_assert!(sampled <= distance + other.length() / 200.0 + tol);
+        }
+    }
 }
@@ -1,9 +1,19 @@
 pub mod basic;
+pub mod error;
 pub mod geo_position;
+pub mod geodesic;
+pub mod index;
 pub mod line;
+pub mod polygon;
 pub mod polyline;
+pub mod projection;
 
 pub use basic::Point;
+pub use error::GeometryError;
 pub use geo_position::GeoPosition;
-pub use line::LineSegment;
+pub use geodesic::DistanceModel;
+pub use index::SegmentIndex;
+pub use line::{orientation, ClosestPoint, LineSegment, Orientation, SegmentIntersection};
+pub use polygon::{BoundingBox, Polygon};
 pub use polyline::PolyLine;
+pub use projection::{Projection, ProjectionMethod};
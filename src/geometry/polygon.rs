@@ -1,8 +1,47 @@
 use std::fmt::Display;
 
 use super::basic::{Point, TOL};
-use super::line::LineSegment;
+use super::error::{GeometryError, Result};
+use super::line::{orientation, LineSegment, Orientation};
+use super::polyline::segments;
 
+#[derive(Clone, Debug)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    // None when there is no point
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Point>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut bbox = BoundingBox {
+            min: first.clone(),
+            max: first.clone(),
+        };
+        for point in points {
+            bbox.min.x = bbox.min.x.min(point.x);
+            bbox.min.y = bbox.min.y.min(point.y);
+            bbox.max.x = bbox.max.x.max(point.x);
+            bbox.max.y = bbox.max.y.max(point.y);
+        }
+        Some(bbox)
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    // zero when the point is inside, nothing within the box can be closer than this
+    pub fn distance_to(&self, point: &Point) -> f64 {
+        let dx = (self.min.x - point.x).max(point.x - self.max.x).max(0.0);
+        let dy = (self.min.y - point.y).max(point.y - self.max.y).max(0.0);
+        dx.hypot(dy)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Polygon {
     lines: Vec<LineSegment>,
 }
@@ -10,46 +49,133 @@ pub struct Polygon {
 impl Display for Polygon {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut str_buf = String::new();
-        str_buf.push_str("[");
+        str_buf.push('[');
         for line in &self.lines {
             str_buf.push_str(&format!("{line} ,"))
         }
-        str_buf.push_str("]");
+        str_buf.push(']');
         write!(f, "{str_buf}")
     }
 }
 
 impl Polygon {
-    pub fn new(points: Vec<Point>) -> Self {
-        if points.len() < 3 || (points.len() == 3 && points[0].distance_to(&points[2]) < TOL) {
-            panic!(
-                "At least 3 points are necessary to form a polygon, but got {:?}",
-                points
-            )
-        }
-        let mut idx = 0;
-        let mut lines = Vec::<LineSegment>::new();
-        while idx < points.len() - 1 {
-            lines.push(LineSegment::new(
-                points[idx].clone(),
-                points[idx + 1].clone(),
-            ));
-            idx += 1;
-        }
+    pub fn new(points: Vec<Point>) -> Result<Self> {
+        let mut ring = points;
         // we need to form a closed shape, if the last point is not same as the first point.
-        if points[0].distance_to(&points[points.len() - 1]) > TOL {
-            lines.push(LineSegment::new(
-                points[points.len() - 1].clone(),
-                points[0].clone(),
-            ));
+        match (ring.first(), ring.last()) {
+            (Some(first), Some(last)) if first.distance_to(last) > TOL => ring.push(first.clone()),
+            _ => {}
         }
-
-        Polygon { lines }
+        // the closing point is not counted
+        let distinct = ring.len().saturating_sub(1);
+        if distinct < 3 {
+            return Err(GeometryError::TooFewPoints {
+                expected: 3,
+                got: distinct,
+            });
+        }
+        let lines = segments(&ring)?;
+        Ok(Polygon { lines })
     }
 
     pub fn get_vertices(&self) -> Vec<Point> {
         self.lines.iter().map(|line| line.a.clone()).collect()
     }
+
+    // Winding number, non-zero when the point is inside. A point on the boundary is always
+    // inside, no matter which way the boundary goes around it.
+    pub fn contains(&self, point: &Point) -> bool {
+        let mut winding = 0;
+        for line in &self.lines {
+            // on the boundary by the same tolerance as the segments themselves
+            if line.contains_point(point) {
+                return true;
+            }
+            let (a, b) = (&line.a, &line.b);
+            let side = orientation(a, b, point);
+            if a.y <= point.y {
+                if b.y > point.y && side == Orientation::CounterClockwise {
+                    winding += 1;
+                }
+            } else if b.y <= point.y && side == Orientation::Clockwise {
+                winding -= 1;
+            }
+        }
+        winding != 0
+    }
+
+    // zero when the point is inside
+    pub fn distance_to(&self, point: &Point) -> f64 {
+        if self.contains(point) {
+            return 0.0;
+        }
+        self.lines
+            .iter()
+            .map(|line| line.distance_to_point(point))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    // The closest points between the polygon and a segment, on the polygon and then on the segment.
+    // Both are the same point when the segment is inside or crosses the boundary.
+    pub fn closest_points_to_segment(&self, segment: &LineSegment) -> (Point, Point) {
+        if self.contains(&segment.a) {
+            return (segment.a.clone(), segment.a.clone());
+        }
+        self.lines
+            .iter()
+            .map(|line| line.closest_points_to_segment(segment))
+            .min_by(|(p0, q0), (p1, q1)| p0.distance_to(q0).total_cmp(&p1.distance_to(q1)))
+            .expect("a polygon has at least 2 lines")
+    }
+
+    // zero when the segment is inside or crosses the boundary
+    pub fn distance_to_segment(&self, segment: &LineSegment) -> f64 {
+        let (p, q) = self.closest_points_to_segment(segment);
+        p.distance_to(&q)
+    }
+
+    // shoelace formula, positive when the vertices go counterclockwise
+    fn signed_area(&self) -> f64 {
+        self.lines
+            .iter()
+            .map(|line| line.a.x * line.b.y - line.b.x * line.a.y)
+            .sum::<f64>()
+            / 2.0
+    }
+
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+
+    // centroid of the area, or the mean of the vertices when there is no area to speak of,
+    // e.g. all vertices are on a line.
+    pub fn centroid(&self) -> Point {
+        let signed_area = self.signed_area();
+        let bbox = self.bounding_box();
+        if signed_area.abs() <= TOL * bbox.min.distance_to(&bbox.max).powi(2) {
+            let vertices = self.get_vertices();
+            let count = vertices.len() as f64;
+            return Point {
+                x: vertices.iter().map(|p| p.x).sum::<f64>() / count,
+                y: vertices.iter().map(|p| p.y).sum::<f64>() / count,
+            };
+        }
+        let (mut x, mut y) = (0.0, 0.0);
+        for line in &self.lines {
+            let cross = line.a.x * line.b.y - line.b.x * line.a.y;
+            x += (line.a.x + line.b.x) * cross;
+            y += (line.a.y + line.b.y) * cross;
+        }
+        Point {
+            x: x / (6.0 * signed_area),
+            y: y / (6.0 * signed_area),
+        }
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(self.lines.iter().map(|line| &line.a))
+            .expect("a polygon has at least 3 points")
+    }
 }
 
 #[cfg(test)]
@@ -80,39 +206,261 @@ impl Polygon {
 mod test {
     use super::super::basic::Point;
     use super::*;
+    use crate::geometry::basic::test_utils::assert_close_to;
 
     #[test]
-    #[should_panic(expected = "At least 3 points are necessary to form a polygon")]
     fn polygon_failed_new_two_points() {
-        Polygon::new(Vec::from([
+        let polygon = Polygon::new(Vec::from([
             Point { x: 0.0, y: 0.0 },
             Point { x: 1.0, y: 1.0 },
         ]));
+        assert!(matches!(
+            polygon,
+            Err(GeometryError::TooFewPoints {
+                expected: 3,
+                got: 2
+            })
+        ));
     }
 
     #[test]
-    #[should_panic(expected = "At least 3 points are necessary to form a polygon")]
     fn polygon_failed_new_three_closed_points() {
-        Polygon::new(Vec::from([
+        let polygon = Polygon::new(Vec::from([
             Point { x: 0.0, y: 0.0 },
             Point { x: 1.0, y: 1.0 },
             Point { x: 0.0, y: 0.0 },
         ]));
+        assert!(matches!(
+            polygon,
+            Err(GeometryError::TooFewPoints {
+                expected: 3,
+                got: 2
+            })
+        ));
+        assert!(Polygon::new(Vec::new()).is_err());
     }
 
     #[test]
-    fn polygon_new_points_not_closed() {
+    fn polygon_failed_new_duplicate_points() {
         let polygon = Polygon::new(Vec::from([
             Point { x: 0.0, y: 0.0 },
             Point { x: 1.0, y: 1.0 },
+            Point { x: 1.0, y: 1.0 },
             Point { x: 1.0, y: 0.0 },
         ]));
+        assert!(matches!(
+            polygon,
+            Err(GeometryError::DuplicatePoint { index: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn polygon_new_points_not_closed() {
+        let polygon = Polygon::new(Vec::from([
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 1.0, y: 0.0 },
+        ]))
+        .unwrap();
         let expected_polygon = Polygon::new(Vec::from([
             Point { x: 0.0, y: 0.0 },
             Point { x: 1.0, y: 1.0 },
             Point { x: 1.0, y: 0.0 },
             Point { x: 0.0, y: 0.0 },
-        ]));
+        ]))
+        .unwrap();
         polygon.assert_close_to(&expected_polygon, 1e-10)
     }
+
+    fn square() -> Polygon {
+        Polygon::new(Vec::from([
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 2.0, y: 0.0 },
+            Point { x: 2.0, y: 2.0 },
+            Point { x: 0.0, y: 2.0 },
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn polygon_contains() {
+        assert!(square().contains(&Point { x: 1.0, y: 1.0 }));
+        assert!(!square().contains(&Point { x: 3.0, y: 1.0 }));
+        assert!(!square().contains(&Point { x: 1.0, y: -0.5 }));
+    }
+
+    #[test]
+    fn polygon_contains_concave() {
+        // a U shape opening to the top
+        let polygon = Polygon::new(Vec::from([
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 3.0, y: 0.0 },
+            Point { x: 3.0, y: 3.0 },
+            Point { x: 2.0, y: 3.0 },
+            Point { x: 2.0, y: 1.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 1.0, y: 3.0 },
+            Point { x: 0.0, y: 3.0 },
+        ]))
+        .unwrap();
+        assert!(!polygon.contains(&Point { x: 1.5, y: 2.0 }));
+        assert!(polygon.contains(&Point { x: 0.5, y: 2.0 }));
+        assert!(polygon.contains(&Point { x: 1.5, y: 0.5 }));
+    }
+
+    #[test]
+    fn polygon_distance_to() {
+        assert_eq!(square().distance_to(&Point { x: 1.0, y: 1.5 }), 0.0);
+        assert!((square().distance_to(&Point { x: 5.0, y: 1.0 }) - 3.0).abs() < 1e-10);
+        assert!((square().distance_to(&Point { x: 5.0, y: 6.0 }) - 5.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn polygon_contains_on_boundary() {
+        // edges, vertices, and both orientations of the same square
+        let clockwise = Polygon::new(square().get_vertices().into_iter().rev().collect()).unwrap();
+        for polygon in [square(), clockwise] {
+            assert!(polygon.contains(&Point { x: 1.0, y: 0.0 }));
+            assert!(polygon.contains(&Point { x: 2.0, y: 1.0 }));
+            assert!(polygon.contains(&Point { x: 1.0, y: 2.0 }));
+            assert!(polygon.contains(&Point { x: 0.0, y: 1.3 }));
+            assert!(polygon.contains(&Point { x: 0.0, y: 0.0 }));
+            assert!(polygon.contains(&Point { x: 2.0, y: 2.0 }));
+            // on the extension of an edge, but outside
+            assert!(!polygon.contains(&Point { x: 3.0, y: 0.0 }));
+            assert!(!polygon.contains(&Point { x: 0.0, y: -1.0 }));
+        }
+    }
+
+    #[test]
+    fn polygon_contains_on_boundary_like_segment() {
+        // projected coordinates are far from the origin, a point a hair off an edge is on it for
+        // the edge as a segment, so it is for the polygon too
+        let (x, y) = (300_000.0, 6_200_000.0);
+        let polygon = Polygon::new(Vec::from([
+            Point { x, y },
+            Point { x: x + 1_000.0, y },
+            Point {
+                x: x + 1_000.0,
+                y: y + 1_000.0,
+            },
+            Point { x, y: y + 1_000.0 },
+        ]))
+        .unwrap();
+        let point = Point {
+            x: x + 500.0,
+            y: y - 1e-7,
+        };
+        let edge = LineSegment::new(Point { x, y }, Point { x: x + 1_000.0, y });
+        assert!(edge.contains_point(&point));
+        assert!(polygon.contains(&point));
+        assert_eq!(polygon.distance_to(&point), 0.0);
+    }
+
+    #[test]
+    fn polygon_contains_level_with_vertex() {
+        // a diamond, the ray from the point passes through the vertex at (2, 1)
+        let polygon = Polygon::new(Vec::from([
+            Point { x: 1.0, y: 0.0 },
+            Point { x: 2.0, y: 1.0 },
+            Point { x: 1.0, y: 2.0 },
+            Point { x: 0.0, y: 1.0 },
+        ]))
+        .unwrap();
+        assert!(polygon.contains(&Point { x: 1.0, y: 1.0 }));
+        assert!(!polygon.contains(&Point { x: -1.0, y: 1.0 }));
+        assert!(!polygon.contains(&Point { x: 3.0, y: 1.0 }));
+    }
+
+    #[test]
+    fn polygon_area() {
+        assert_close_to(square().area(), 4.0, 1e-12);
+        let clockwise = Polygon::new(square().get_vertices().into_iter().rev().collect()).unwrap();
+        assert_close_to(clockwise.area(), 4.0, 1e-12);
+        let triangle = Polygon::new(Vec::from([
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 4.0, y: 0.0 },
+            Point { x: 0.0, y: 3.0 },
+        ]))
+        .unwrap();
+        assert_close_to(triangle.area(), 6.0, 1e-12);
+    }
+
+    #[test]
+    fn polygon_centroid() {
+        square()
+            .centroid()
+            .assert_close_to(&Point { x: 1.0, y: 1.0 }, 1e-12);
+        // an L shape, made of a 2x1 and a 1x1 rectangle
+        let polygon = Polygon::new(Vec::from([
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 2.0, y: 0.0 },
+            Point { x: 2.0, y: 1.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 1.0, y: 2.0 },
+            Point { x: 0.0, y: 2.0 },
+        ]))
+        .unwrap();
+        polygon.centroid().assert_close_to(
+            &Point {
+                x: (2.0 * 1.0 + 1.0 * 0.5) / 3.0,
+                y: (2.0 * 0.5 + 1.0 * 1.5) / 3.0,
+            },
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn polygon_centroid_without_area() {
+        let polygon = Polygon::new(Vec::from([
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 2.0, y: 2.0 },
+        ]))
+        .unwrap();
+        polygon
+            .centroid()
+            .assert_close_to(&Point { x: 1.0, y: 1.0 }, 1e-12);
+    }
+
+    #[test]
+    fn polygon_bounding_box() {
+        let polygon = Polygon::new(Vec::from([
+            Point { x: -1.0, y: 0.5 },
+            Point { x: 3.0, y: -2.0 },
+            Point { x: 2.0, y: 4.0 },
+        ]))
+        .unwrap();
+        let bbox = polygon.bounding_box();
+        bbox.min.assert_close_to(&Point { x: -1.0, y: -2.0 }, 1e-12);
+        bbox.max.assert_close_to(&Point { x: 3.0, y: 4.0 }, 1e-12);
+        assert!(bbox.contains(&Point { x: 0.0, y: 0.0 }));
+        assert!(!bbox.contains(&Point { x: 0.0, y: 5.0 }));
+        assert_eq!(bbox.distance_to(&Point { x: 0.0, y: 0.0 }), 0.0);
+        assert_close_to(bbox.distance_to(&Point { x: 0.0, y: 5.0 }), 1.0, 1e-12);
+        assert_close_to(bbox.distance_to(&Point { x: 6.0, y: -6.0 }), 5.0, 1e-12);
+        let from_points = BoundingBox::from_points(&polygon.get_vertices()).unwrap();
+        from_points.min.assert_close_to(&bbox.min, 1e-12);
+        from_points.max.assert_close_to(&bbox.max, 1e-12);
+        assert!(BoundingBox::from_points(&[]).is_none());
+    }
+
+    #[test]
+    fn polygon_distance_to_segment() {
+        let polygon = square();
+        // crossing the boundary
+        let crossing = LineSegment::new(Point { x: -1.0, y: 0.5 }, Point { x: 0.5, y: 0.5 });
+        assert_eq!(polygon.distance_to_segment(&crossing), 0.0);
+        // passing through without any end inside
+        let through = LineSegment::new(Point { x: -1.0, y: 0.5 }, Point { x: 3.0, y: 0.5 });
+        assert_eq!(polygon.distance_to_segment(&through), 0.0);
+        // inside
+        let inside = LineSegment::new(Point { x: 0.2, y: 0.2 }, Point { x: 0.8, y: 0.8 });
+        assert_eq!(polygon.distance_to_segment(&inside), 0.0);
+        let outside = LineSegment::new(Point { x: -1.0, y: 3.0 }, Point { x: 2.0, y: 3.0 });
+        let (on_polygon, on_segment) = polygon.closest_points_to_segment(&outside);
+        assert_close_to(on_polygon.y, 2.0, 1e-12);
+        assert_close_to(on_segment.y, 3.0, 1e-12);
+        assert_close_to(polygon.distance_to_segment(&outside), 1.0, 1e-12);
+    }
 }
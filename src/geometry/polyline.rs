@@ -1,63 +1,95 @@
-const NUM_THREADS: u8 = 2;
+use super::{
+    basic::{Point, TOL},
+    error::{GeometryError, Result},
+    line::{ClosestPoint, LineSegment},
+};
 
-use std::sync::{Arc, Mutex};
-
-use super::{basic::Point, line::LineSegment};
+// A segment between each pair of consecutive points, which must not be the same.
+pub(super) fn segments(points: &[Point]) -> Result<Vec<LineSegment>> {
+    points
+        .windows(2)
+        .enumerate()
+        .map(|(idx, pair)| {
+            if pair[0].distance_to(&pair[1]) < TOL {
+                return Err(GeometryError::DuplicatePoint {
+                    index: idx + 1,
+                    point: pair[1].clone(),
+                });
+            }
+            Ok(LineSegment::new(pair[0].clone(), pair[1].clone()))
+        })
+        .collect()
+}
 
+#[derive(Clone)]
 pub struct PolyLine {
     lines: Vec<LineSegment>,
 }
 
 impl PolyLine {
-    pub fn new(points: Vec<Point>) -> Self {
+    pub fn new(points: Vec<Point>) -> Result<Self> {
         if points.len() < 2 {
-            panic!(
-                "need at least two points to form a polyline, got '{:?}'",
-                points
-            )
+            return Err(GeometryError::TooFewPoints {
+                expected: 2,
+                got: points.len(),
+            });
         }
-        let mut lines = Vec::<LineSegment>::new();
-        let mut idx = 0;
-        while idx < points.len() - 1 {
-            lines.push(LineSegment::new(
-                points[idx].clone(),
-                points[idx + 1].clone(),
-            ));
-            idx += 1;
-        }
-        PolyLine { lines }
+        let lines = segments(&points)?;
+        Ok(PolyLine { lines })
     }
 
     pub fn distance_to(&self, point: &Point) -> f64 {
-        std::thread::scope(|scope| {
-            let counter = Arc::new(Mutex::<usize>::new(0));
-            let mut threads = Vec::new();
-            for _ in 0..NUM_THREADS {
-                let counter = Arc::clone(&counter);
-                threads.push(scope.spawn(move || {
-                    let mut distances = Vec::new();
-                    loop {
-                        let line_idx = {
-                            let mut idx = counter.lock().unwrap();
-                            let line_idx = *idx;
-                            *idx += 1;
-                            line_idx
-                        };
-                        if line_idx >= self.lines.len() {
-                            break;
-                        }
-                        distances.push(self.lines[line_idx].distance_to_point(&point));
-                    }
-                    distances
-                }));
-            }
+        self.lines
+            .iter()
+            .map(|line| line.distance_to_point(point))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn get_vertices(&self) -> Vec<Point> {
+        let mut vertices = self
+            .lines
+            .iter()
+            .map(|line| line.a.clone())
+            .collect::<Vec<Point>>();
+        // a polyline has at least one segment
+        vertices.push(self.lines[self.lines.len() - 1].b.clone());
+        vertices
+    }
+
+    pub fn length(&self) -> f64 {
+        self.lines.iter().map(|line| line.length()).sum()
+    }
+
+    pub fn length_within(&self, center: &Point, radius: f64) -> f64 {
+        self.lines
+            .iter()
+            .map(|line| line.length_within(center, radius))
+            .sum()
+    }
 
-            let mut distances: Vec<f64> = Vec::new();
-            for thread in threads {
-                distances.extend(thread.join().unwrap());
+    pub fn segments(&self) -> &[LineSegment] {
+        &self.lines
+    }
+
+    pub fn closest_point_to(&self, point: &Point) -> ClosestPoint {
+        let mut closest: Option<ClosestPoint> = None;
+        let mut length_before = 0.0;
+        for (idx, line) in self.lines.iter().enumerate() {
+            let candidate = line.closest_point_to(point);
+            if closest
+                .as_ref()
+                .is_none_or(|c| candidate.distance < c.distance)
+            {
+                closest = Some(ClosestPoint {
+                    segment_index: idx,
+                    distance_along: length_before + candidate.distance_along,
+                    ..candidate
+                });
             }
-            distances.iter().fold(f64::INFINITY, |a, d| a.min(*d))
-        })
+            length_before += line.length();
+        }
+        // a polyline has at least one segment
+        closest.unwrap()
     }
 }
 
@@ -97,15 +129,101 @@ mod test {
             Point { x: 0.0, y: 1.0 },
             Point { x: 0.0, y: 0.0 },
             Point { x: 1.0, y: 0.0 },
-        ]));
+        ]))
+        .unwrap();
         let min_distance = polyline.distance_to(&Point { x: -0.5, y: 0.5 });
         assert_close_to(min_distance, 0.5, 1e-10);
     }
 
     #[test]
-    #[should_panic]
+    fn polyline_closest_point_to() {
+        let polyline = PolyLine::new(Vec::from([
+            Point { x: 0.0, y: 1.0 },
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 0.0 },
+        ]))
+        .unwrap();
+        let closest = polyline.closest_point_to(&Point { x: 0.8, y: -0.5 });
+        assert_close_to(closest.distance, 0.5, 1e-10);
+        closest
+            .point
+            .assert_close_to(&Point { x: 0.8, y: 0.0 }, 1e-10);
+        assert_eq!(closest.segment_index, 1);
+        assert_close_to(closest.segment_t, 0.8, 1e-10);
+        assert_close_to(closest.distance_along, 1.8, 1e-10);
+    }
+
+    #[test]
+    fn polyline_closest_point_to_first_segment() {
+        let polyline = PolyLine::new(Vec::from([
+            Point { x: 0.0, y: 1.0 },
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 0.0 },
+        ]))
+        .unwrap();
+        let closest = polyline.closest_point_to(&Point { x: -0.5, y: 0.8 });
+        assert_eq!(closest.segment_index, 0);
+        closest
+            .point
+            .assert_close_to(&Point { x: 0.0, y: 0.8 }, 1e-10);
+        polyline.segments()[closest.segment_index].assert_close_to(
+            &LineSegment::new(Point { x: 0.0, y: 1.0 }, Point { x: 0.0, y: 0.0 }),
+            1e-10,
+        );
+    }
+
+    #[test]
+    fn polyline_get_vertices() {
+        let points = Vec::from([
+            Point { x: 0.0, y: 1.0 },
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 0.0 },
+        ]);
+        let vertices = PolyLine::new(points.clone()).unwrap().get_vertices();
+        assert_eq!(vertices.len(), 3);
+        for (vertex, point) in vertices.iter().zip(points.iter()) {
+            vertex.assert_close_to(point, 1e-14);
+        }
+    }
+
+    #[test]
+    fn polyline_length_within() {
+        let polyline = PolyLine::new(Vec::from([
+            Point { x: -10.0, y: 0.0 },
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 0.0, y: 10.0 },
+        ]))
+        .unwrap();
+        assert_close_to(polyline.length(), 20.0, 1e-10);
+        assert_close_to(
+            polyline.length_within(&Point { x: 0.0, y: 0.0 }, 2.0),
+            4.0,
+            1e-10,
+        );
+    }
+
+    #[test]
     fn polyline_failed_one_point() {
-        PolyLine::new(Vec::from([Point { x: 0.0, y: 1.0 }]));
+        assert!(matches!(
+            PolyLine::new(Vec::from([Point { x: 0.0, y: 1.0 }])),
+            Err(GeometryError::TooFewPoints {
+                expected: 2,
+                got: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn polyline_failed_duplicate_points() {
+        let polyline = PolyLine::new(Vec::from([
+            Point { x: 0.0, y: 1.0 },
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 0.0, y: 0.0 },
+        ]));
+        assert!(matches!(
+            polyline,
+            Err(GeometryError::DuplicatePoint { index: 2, .. })
+        ));
     }
 
     #[test]
@@ -113,6 +231,7 @@ mod test {
         PolyLine::new(Vec::from([
             Point { x: 0.0, y: 1.0 },
             Point { x: 0.0, y: 0.0 },
-        ]));
+        ]))
+        .unwrap();
     }
 }
@@ -0,0 +1,145 @@
+use super::basic::Point;
+use super::geo_position::{GeoPosition, EARTH_RADIUS_KM, SYDNEY_CENTRAL_POSITION};
+
+#[derive(Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum ProjectionMethod {
+    // x is scaled by the cosine of the latitude of the projected position itself.
+    Sinusoidal,
+    // x is scaled by the cosine of the latitude of the origin.
+    Equirectangular,
+}
+
+// Projects lat/lon to a local plane in meter, tangent at the origin.
+// The further from the origin, the larger the distortion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    pub origin: GeoPosition,
+    pub method: ProjectionMethod,
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Projection {
+            origin: SYDNEY_CENTRAL_POSITION.clone(),
+            method: ProjectionMethod::Sinusoidal,
+        }
+    }
+}
+
+impl Projection {
+    pub fn new(origin: GeoPosition, method: ProjectionMethod) -> Self {
+        Projection { origin, method }
+    }
+
+    fn x_scale(&self, latitude_radius: f64) -> f64 {
+        let latitude_radius = match self.method {
+            ProjectionMethod::Sinusoidal => latitude_radius,
+            ProjectionMethod::Equirectangular => self.origin.latitude_radius,
+        };
+        EARTH_RADIUS_KM * 1000.0 * latitude_radius.cos()
+    }
+
+    pub fn project(&self, position: &GeoPosition) -> Point {
+        let x = self.x_scale(position.latitude_radius)
+            * (position.longitude_radius - self.origin.longitude_radius);
+        let y = EARTH_RADIUS_KM * 1000.0 * (position.latitude_radius - self.origin.latitude_radius);
+        Point { x, y }
+    }
+
+    // exact inverse of `project`, the latitude is recovered first since the scale of x
+    // depends on it.
+    pub fn unproject(&self, point: &Point) -> GeoPosition {
+        let latitude_radius = point.y / (EARTH_RADIUS_KM * 1000.0) + self.origin.latitude_radius;
+        let longitude_radius =
+            point.x / self.x_scale(latitude_radius) + self.origin.longitude_radius;
+        GeoPosition {
+            latitude_radius,
+            longitude_radius,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::basic::test_utils::assert_close_to;
+    use crate::geometry::geodesic::haversine_distance;
+
+    #[test]
+    fn default_same_as_to_cartesian() {
+        let position = GeoPosition::from_degree(-33.921119441679096, 151.1984099658811);
+        Projection::default()
+            .project(&position)
+            .assert_close_to(&position.to_cartesian(), 1e-9);
+    }
+
+    #[test]
+    fn origin_projected_to_zero() {
+        let newcastle = GeoPosition::from_degree(-32.9283, 151.7817);
+        for method in [
+            ProjectionMethod::Sinusoidal,
+            ProjectionMethod::Equirectangular,
+        ] {
+            Projection::new(newcastle.clone(), method)
+                .project(&newcastle)
+                .assert_close_to(&Point { x: 0.0, y: 0.0 }, 1e-9);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let wollongong = GeoPosition::from_degree(-34.4278, 150.8931);
+        for method in [
+            ProjectionMethod::Sinusoidal,
+            ProjectionMethod::Equirectangular,
+        ] {
+            let projection = Projection::new(wollongong.clone(), method);
+            let position = GeoPosition::from_degree(-34.5, 150.8);
+            let round_trip = projection.unproject(&projection.project(&position));
+            assert_close_to(round_trip.latitude_degree(), -34.5, 1e-10);
+            assert_close_to(round_trip.longitude_degree(), 150.8, 1e-10);
+        }
+    }
+
+    #[test]
+    fn less_distortion_near_origin() {
+        // two points 1km apart east-west near Newcastle, ~120km north of Sydney central.
+        // 0.010716 degree of longitude is ~1000m at -32.9283.
+        let a = GeoPosition::from_degree(-32.9283, 151.7817);
+        let b = GeoPosition::from_degree(-32.9283, 151.7817 + 0.010716);
+        let local = Projection::new(a.clone(), ProjectionMethod::Equirectangular);
+        let local_distance = local.project(&a).distance_to(&local.project(&b));
+        assert_close_to(local_distance, 1000.0, 0.5);
+        let sydney = Projection::new(
+            SYDNEY_CENTRAL_POSITION.clone(),
+            ProjectionMethod::Equirectangular,
+        );
+        let sydney_distance = sydney.project(&a).distance_to(&sydney.project(&b));
+        let expected = haversine_distance(&a, &b);
+        assert!((local_distance - expected).abs() < (sydney_distance - expected).abs());
+    }
+
+    #[test]
+    fn local_origin_less_distortion_than_sydney() {
+        // a span ~1km long near Broken Hill, ~1000km west of Sydney central
+        let a = GeoPosition::from_degree(-31.9505, 141.4533);
+        let b = GeoPosition::from_degree(-31.9445, 141.4612);
+        let expected = haversine_distance(&a, &b);
+        for method in [
+            ProjectionMethod::Sinusoidal,
+            ProjectionMethod::Equirectangular,
+        ] {
+            let error = |projection: Projection| {
+                (projection.project(&a).distance_to(&projection.project(&b)) - expected).abs()
+            };
+            let local_error = error(Projection::new(a.clone(), method.clone()));
+            let sydney_error = error(Projection::new(SYDNEY_CENTRAL_POSITION.clone(), method));
+            assert!(
+                local_error < sydney_error,
+                "local error {} is not less than {} with the Sydney origin",
+                local_error,
+                sydney_error
+            );
+        }
+    }
+}
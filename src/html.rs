@@ -0,0 +1,386 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{HvError, Result};
+use crate::geometry::Point;
+use crate::query::{DistanceReport, SearchDetails};
+use crate::style::{escape, voltage_colour, RANGE_RINGS_M};
+
+// width and height of the map in pixel
+const MAP_SIZE: f64 = 800.0;
+// space around everything drawn, as a fraction of the extent
+const MAP_MARGIN: f64 = 0.1;
+
+// Maps the projected plane to the svg, north up.
+struct Viewport {
+    min_x: f64,
+    max_y: f64,
+    scale: f64,
+}
+
+impl Viewport {
+    // covers the range rings, the lot, the nearest points and the suburbs searched
+    fn new(report: &DistanceReport, details: &SearchDetails) -> Self {
+        let ring = RANGE_RINGS_M.iter().fold(0.0_f64, |r, v| r.max(*v));
+        let corners = [
+            Point {
+                x: report.location.x - ring,
+                y: report.location.y - ring,
+            },
+            Point {
+                x: report.location.x + ring,
+                y: report.location.y + ring,
+            },
+        ];
+        let lot = report
+            .lot
+            .as_ref()
+            .map_or(Vec::new(), |lot| lot.get_vertices());
+        let points = corners
+            .iter()
+            .chain(lot.iter())
+            .chain(report.distances.iter().map(|d| &d.nearest_point))
+            .chain(details.suburbs.iter().map(|s| &s.info.location));
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        );
+        for point in points {
+            min_x = min_x.min(point.x);
+            min_y = min_y.min(point.y);
+            max_x = max_x.max(point.x);
+            max_y = max_y.max(point.y);
+        }
+        let extent = (max_x - min_x).max(max_y - min_y);
+        let margin = extent * MAP_MARGIN;
+        // centered on the extent, which is square
+        let center_x = (min_x + max_x) / 2.0;
+        let center_y = (min_y + max_y) / 2.0;
+        let half = extent / 2.0 + margin;
+        Viewport {
+            min_x: center_x - half,
+            max_y: center_y + half,
+            scale: MAP_SIZE / (2.0 * half),
+        }
+    }
+
+    fn to_svg(&self, point: &Point) -> (f64, f64) {
+        (
+            (point.x - self.min_x) * self.scale,
+            (self.max_y - point.y) * self.scale,
+        )
+    }
+
+    fn svg_point(&self, point: &Point) -> String {
+        let (x, y) = self.to_svg(point);
+        format!("{:.1},{:.1}", x, y)
+    }
+}
+
+fn svg_map(svg: &mut String, report: &DistanceReport, details: &SearchDetails) {
+    let viewport = Viewport::new(report, details);
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+        MAP_SIZE
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="{0}" height="{0}" fill="#f8f8f4" stroke="#cccccc"/>"##,
+        MAP_SIZE
+    );
+
+    // lower voltages first, so that higher ones are drawn on top
+    let mut voltages = details.high_voltage_lines.keys().collect::<Vec<&u16>>();
+    voltages.sort();
+    for voltage in voltages {
+        let _ = writeln!(
+            svg,
+            r#"<g class="line" stroke="{}" stroke-width="2" fill="none">"#,
+            voltage_colour(*voltage).to_html()
+        );
+        for line in details.high_voltage_lines[voltage].iter() {
+            let points = line
+                .line
+                .get_vertices()
+                .iter()
+                .map(|p| viewport.svg_point(p))
+                .collect::<Vec<String>>()
+                .join(" ");
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}"><title>{}kV line {}</title></polyline>"#,
+                points,
+                voltage,
+                escape(&line.id)
+            );
+        }
+        svg.push_str("</g>\n");
+    }
+
+    for suburb in details.suburbs.iter() {
+        let (x, y) = viewport.to_svg(&suburb.info.location);
+        let _ = writeln!(
+            svg,
+            r##"<g class="suburb"><circle cx="{:.1}" cy="{:.1}" r="3" fill="#3366cc"/><text x="{:.1}" y="{:.1}" font-size="11" fill="#3366cc">{}</text></g>"##,
+            x,
+            y,
+            x + 5.0,
+            y - 5.0,
+            escape(&suburb.info.name)
+        );
+    }
+
+    if let Some(lot) = &report.lot {
+        let points = lot
+            .get_vertices()
+            .iter()
+            .map(|p| viewport.svg_point(p))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let _ = writeln!(
+            svg,
+            r##"<polygon class="lot" points="{}" fill="#000000" fill-opacity="0.1" stroke="#000000" stroke-width="1"><title>lot</title></polygon>"##,
+            points
+        );
+    }
+
+    let (query_x, query_y) = viewport.to_svg(&report.location);
+    for radius in RANGE_RINGS_M {
+        let _ = writeln!(
+            svg,
+            r##"<circle class="ring" cx="{:.1}" cy="{:.1}" r="{:.1}" fill="none" stroke="#555555" stroke-dasharray="4 3"><title>{}m</title></circle>"##,
+            query_x,
+            query_y,
+            radius * viewport.scale,
+            radius
+        );
+    }
+    for distance in report.distances.iter() {
+        let (from_x, from_y) = viewport.to_svg(&distance.query_point);
+        let (x, y) = viewport.to_svg(&distance.nearest_point);
+        let _ = writeln!(
+            svg,
+            r##"<line class="connector" x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="1" stroke-dasharray="2 2"><title>{:.0}m to {}kV</title></line>"##,
+            from_x,
+            from_y,
+            x,
+            y,
+            voltage_colour(distance.voltage).to_html(),
+            distance.distance_m,
+            distance.voltage
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r##"<circle class="query" cx="{:.1}" cy="{:.1}" r="5" fill="#000000"/>"##,
+        query_x, query_y
+    );
+    svg.push_str("</svg>\n");
+}
+
+fn distance_table(html: &mut String, report: &DistanceReport) {
+    html.push_str(
+        "<table>\n<tr><th>voltage</th><th>distance</th><th>line</th><th>nearest point</th><th>band</th></tr>\n",
+    );
+    for distance in report.distances.iter() {
+        let _ = writeln!(
+            html,
+            r#"<tr><td><span style="color:{}">&#9632;</span> {}kV</td><td>{:.0}m</td><td>{}</td><td>{:.6}, {:.6}</td><td>{}</td></tr>"#,
+            voltage_colour(distance.voltage).to_html(),
+            distance.voltage,
+            distance.distance_m,
+            escape(&distance.line_id),
+            distance.nearest_position.latitude_degree(),
+            distance.nearest_position.longitude_degree(),
+            distance.band
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+// A single html file with everything inline, so that it renders offline.
+pub fn report_to_html(report: &DistanceReport, details: &SearchDetails) -> String {
+    let title = report.full_address.as_deref().unwrap_or("query");
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>", escape(title));
+    html.push_str(
+        "<style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse;margin-top:1em}th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}</style>\n",
+    );
+    html.push_str("</head>\n<body>\n");
+    let _ = writeln!(html, "<h1>{}</h1>", escape(title));
+    let _ = writeln!(
+        html,
+        "<p>{:.6}, {:.6}{}</p>",
+        report.position.latitude_degree(),
+        report.position.longitude_degree(),
+        report
+            .suburb
+            .as_ref()
+            .map_or(String::new(), |s| format!(", located in {}", escape(s)))
+    );
+    let _ = writeln!(
+        html,
+        "<p>searched {} suburbs within {:.1}km</p>",
+        report.suburbs_searched.len(),
+        report.searched_radius_m / 1000.0
+    );
+    if report.distances.is_empty() {
+        html.push_str("<p>no high voltage power line found in this area.</p>\n");
+    } else {
+        distance_table(&mut html, report);
+    }
+    svg_map(&mut html, report, details);
+    let _ = writeln!(
+        html,
+        "<p>dashed rings are {} around the location</p>",
+        RANGE_RINGS_M
+            .iter()
+            .map(|r| format!("{}m", r))
+            .collect::<Vec<String>>()
+            .join(" and ")
+    );
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn write_html_report(
+    path: &Path,
+    report: &DistanceReport,
+    details: &SearchDetails,
+) -> Result<()> {
+    fs::write(path, report_to_html(report, details))
+        .map_err(|e| HvError::ReportOutput(format!("'{}': {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_source::{HighVoltageLine, SuburbInfo};
+    use crate::geometry::{PolyLine, Polygon, Projection};
+    use crate::query::{SearchedSuburb, VoltageDistance};
+    use std::collections::HashMap;
+
+    fn report() -> DistanceReport {
+        DistanceReport {
+            full_address: Some("1 <Main> St".to_owned()),
+            position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            projection: Projection::default(),
+            location: Point { x: 0.0, y: 0.0 },
+            lot: None,
+            distances: vec![VoltageDistance::new_for_test(330, 400.0, "7")],
+            suburb: Some("Frenchs Forest".to_owned()),
+            suburbs_searched: vec!["Frenchs Forest".to_owned()],
+            searched_radius_m: 1500.0,
+        }
+    }
+
+    fn details() -> SearchDetails {
+        let points = vec![Point { x: -10.0, y: 400.0 }, Point { x: 10.0, y: 400.0 }];
+        SearchDetails {
+            suburbs: vec![SearchedSuburb {
+                info: SuburbInfo {
+                    name: "Frenchs Forest".to_owned(),
+                    id: 1,
+                    postcode: 2086,
+                    position: Point { x: 0.0, y: -600.0 }.to_geo_position(),
+                    location: Point { x: 0.0, y: -600.0 },
+                },
+                boundary: None,
+            }],
+            high_voltage_lines: HashMap::from([(
+                330,
+                vec![HighVoltageLine {
+                    positions: points
+                        .iter()
+                        .map(|p| Projection::default().unproject(p))
+                        .collect(),
+                    line: PolyLine::new(points).unwrap(),
+                    id: "7".to_owned(),
+                    suburb_id: 1,
+                }],
+            )]),
+        }
+    }
+
+    #[test]
+    fn self_contained() {
+        let html = report_to_html(&report(), &details());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("src="));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<link"));
+        assert!(html.contains("<svg"));
+    }
+
+    #[test]
+    fn table_of_distances() {
+        let html = report_to_html(&report(), &details());
+        assert!(html.contains("330kV</td><td>400m</td><td>7</td>"));
+        assert!(html.contains("<td>outside 300m</td></tr>"));
+        assert!(html.contains("located in Frenchs Forest"));
+        assert!(html.contains("<h1>1 &lt;Main&gt; St</h1>"));
+    }
+
+    #[test]
+    fn map_drawn_north_up() {
+        let report = report();
+        let details = details();
+        let viewport = Viewport::new(&report, &details);
+        // the extent is from the suburb at -600 to the line at 400, plus margin
+        let (_, query_y) = viewport.to_svg(&report.location);
+        let (_, line_y) = viewport.to_svg(&Point { x: 0.0, y: 400.0 });
+        let (_, suburb_y) = viewport.to_svg(&details.suburbs[0].info.location);
+        assert!(line_y < query_y && query_y < suburb_y);
+        for y in [query_y, line_y, suburb_y] {
+            assert!((0.0..=MAP_SIZE).contains(&y));
+        }
+        let html = report_to_html(&report, &details);
+        assert!(html.contains("<title>330kV line 7</title>"));
+        assert!(html.contains(">Frenchs Forest</text>"));
+        assert_eq!(html.matches(r#"class="ring""#).count(), RANGE_RINGS_M.len());
+    }
+
+    #[test]
+    fn connector_from_lot() {
+        let mut report = report();
+        report.lot = Some(
+            Polygon::new(vec![
+                Point { x: -20.0, y: -20.0 },
+                Point { x: 20.0, y: -20.0 },
+                Point { x: 20.0, y: 20.0 },
+                Point { x: -20.0, y: 20.0 },
+            ])
+            .unwrap(),
+        );
+        report.distances[0].query_point = Point { x: 0.0, y: 20.0 };
+        let details = details();
+        let viewport = Viewport::new(&report, &details);
+        let html = report_to_html(&report, &details);
+        assert_eq!(html.matches(r#"<polygon class="lot""#).count(), 1);
+        let (x, y) = viewport.to_svg(&report.distances[0].query_point);
+        assert!(html.contains(&format!(
+            r#"<line class="connector" x1="{:.1}" y1="{:.1}""#,
+            x, y
+        )));
+    }
+
+    #[test]
+    fn no_line_found() {
+        let mut report = report();
+        report.distances.clear();
+        let html = report_to_html(
+            &report,
+            &SearchDetails {
+                suburbs: vec![],
+                high_voltage_lines: HashMap::new(),
+            },
+        );
+        assert!(html.contains("no high voltage power line found"));
+        assert!(!html.contains("<table>"));
+    }
+}
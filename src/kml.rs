@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::data_source::HighVoltageLine;
+use crate::error::{HvError, Result};
+use crate::geometry::GeoPosition;
+use crate::query::{DistanceReport, SearchDetails};
+use crate::style::{circle, escape, OTHER_VOLTAGE_COLOUR, RANGE_RINGS_M, VOLTAGE_COLOURS};
+
+fn style_id(voltage: u16) -> String {
+    if VOLTAGE_COLOURS.iter().any(|(v, _)| *v == voltage) {
+        format!("hv_{}kv", voltage)
+    } else {
+        "hv_other".to_owned()
+    }
+}
+
+fn coordinates<'a>(positions: impl Iterator<Item = &'a GeoPosition>) -> String {
+    positions
+        .map(|p| format!("{},{},0", p.longitude_degree(), p.latitude_degree()))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn styles(kml: &mut String) {
+    let colours = VOLTAGE_COLOURS
+        .iter()
+        .map(|(voltage, colour)| (style_id(*voltage), *colour))
+        .chain([("hv_other".to_owned(), OTHER_VOLTAGE_COLOUR)]);
+    for (id, colour) in colours {
+        let _ = writeln!(
+            kml,
+            r#"<Style id="{}"><LineStyle><color>{}</color><width>3</width></LineStyle></Style>"#,
+            id,
+            colour.to_kml()
+        );
+    }
+    kml.push_str(
+        r#"<Style id="connector"><LineStyle><color>ffffffff</color><width>2</width></LineStyle><IconStyle><scale>0.6</scale></IconStyle></Style>"#,
+    );
+    kml.push('\n');
+    kml.push_str(
+        r#"<Style id="ring"><LineStyle><color>ffff8000</color><width>1</width></LineStyle><PolyStyle><fill>0</fill></PolyStyle></Style>"#,
+    );
+    kml.push('\n');
+}
+
+// every line in a folder per voltage, higher voltages first
+fn high_voltage_lines(kml: &mut String, high_voltage_lines: &HashMap<u16, Vec<HighVoltageLine>>) {
+    let mut voltages = high_voltage_lines.keys().collect::<Vec<&u16>>();
+    voltages.sort_by_key(|v| std::cmp::Reverse(**v));
+    for voltage in voltages {
+        let _ = writeln!(kml, "<Folder><name>{}kV lines</name>", voltage);
+        for line in high_voltage_lines[voltage].iter() {
+            let _ = writeln!(
+                kml,
+                "<Placemark><name>{}kV line {}</name><styleUrl>#{}</styleUrl><LineString><tessellate>1</tessellate><coordinates>{}</coordinates></LineString></Placemark>",
+                voltage,
+                escape(&line.id),
+                style_id(*voltage),
+                coordinates(line.positions.iter())
+            );
+        }
+        kml.push_str("</Folder>\n");
+    }
+}
+
+// A connector from where each distance is measured to the nearest point of the voltage, labelled at
+// the nearest point.
+fn connectors(kml: &mut String, report: &DistanceReport) {
+    kml.push_str("<Folder><name>Nearest points</name>\n");
+    for distance in report.distances.iter() {
+        let _ = writeln!(
+            kml,
+            "<Placemark><name>{:.0}m to {}kV</name><description>line {}</description><styleUrl>#connector</styleUrl><MultiGeometry><Point><coordinates>{}</coordinates></Point><LineString><tessellate>1</tessellate><coordinates>{}</coordinates></LineString></MultiGeometry></Placemark>",
+            distance.distance_m,
+            distance.voltage,
+            escape(&distance.line_id),
+            coordinates([&distance.nearest_position].into_iter()),
+            coordinates([&distance.query_position, &distance.nearest_position].into_iter())
+        );
+    }
+    kml.push_str("</Folder>\n");
+}
+
+fn range_rings(kml: &mut String, report: &DistanceReport) {
+    kml.push_str("<Folder><name>Range rings</name>\n");
+    for radius in RANGE_RINGS_M {
+        let mut ring = circle(&report.location, radius)
+            .iter()
+            .map(|p| report.projection.unproject(p))
+            .collect::<Vec<GeoPosition>>();
+        ring.push(ring[0].clone());
+        let _ = writeln!(
+            kml,
+            "<Placemark><name>{}m</name><styleUrl>#ring</styleUrl><Polygon><outerBoundaryIs><LinearRing><coordinates>{}</coordinates></LinearRing></outerBoundaryIs></Polygon></Placemark>",
+            radius,
+            coordinates(ring.iter())
+        );
+    }
+    kml.push_str("</Folder>\n");
+}
+
+pub fn report_to_kml(report: &DistanceReport, details: &SearchDetails) -> String {
+    let name = report.full_address.as_deref().unwrap_or("query");
+    let mut kml = String::new();
+    kml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    kml.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n");
+    let _ = writeln!(kml, "<Document><name>{}</name>", escape(name));
+    styles(&mut kml);
+    let _ = writeln!(
+        kml,
+        "<Placemark><name>{}</name><Point><coordinates>{}</coordinates></Point></Placemark>",
+        escape(name),
+        coordinates([&report.position].into_iter())
+    );
+    high_voltage_lines(&mut kml, &details.high_voltage_lines);
+    connectors(&mut kml, report);
+    range_rings(&mut kml, report);
+    kml.push_str("</Document>\n</kml>\n");
+    kml
+}
+
+// A .kmz is the kml zipped as doc.kml.
+fn write_kmz(path: &Path, kml: &str) -> io::Result<()> {
+    let mut zip = ZipWriter::new(fs::File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("doc.kml", options)?;
+    zip.write_all(kml.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+// Written as .kmz when the path ends with .kmz, as plain kml otherwise.
+pub fn export_kml(path: &Path, report: &DistanceReport, details: &SearchDetails) -> Result<()> {
+    let kml = report_to_kml(report, details);
+    let is_kmz = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("kmz"));
+    if is_kmz {
+        write_kmz(path, &kml)
+    } else {
+        fs::write(path, kml)
+    }
+    .map_err(|source| HvError::KmlExport {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::{Point, PolyLine, Projection};
+    use crate::query::VoltageDistance;
+    use std::io::Read;
+
+    fn report() -> DistanceReport {
+        DistanceReport {
+            full_address: Some("1 Smith & Sons St".to_owned()),
+            position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            projection: Projection::default(),
+            location: Point { x: 0.0, y: 0.0 },
+            lot: None,
+            distances: vec![
+                VoltageDistance::new_for_test(330, 120.4, "7"),
+                VoltageDistance::new_for_test(11, 12.6, "b"),
+            ],
+            suburb: None,
+            suburbs_searched: vec![],
+            searched_radius_m: 0.0,
+        }
+    }
+
+    fn details() -> SearchDetails {
+        let line = |id: &str, y: f64| {
+            let points = vec![Point { x: -10.0, y }, Point { x: 10.0, y }];
+            HighVoltageLine {
+                positions: points
+                    .iter()
+                    .map(|p| Projection::default().unproject(p))
+                    .collect(),
+                line: PolyLine::new(points).unwrap(),
+                id: id.to_owned(),
+                suburb_id: 1,
+            }
+        };
+        SearchDetails {
+            suburbs: vec![],
+            high_voltage_lines: HashMap::from([
+                (330, vec![line("7", 120.4)]),
+                (11, vec![line("b", 12.5)]),
+            ]),
+        }
+    }
+
+    #[test]
+    fn lines_styled_by_voltage() {
+        let kml = report_to_kml(&report(), &details());
+        assert!(kml.contains(r#"<Style id="hv_330kv"><LineStyle><color>ff1c1ae4</color>"#));
+        assert!(kml.contains("<name>330kV line 7</name><styleUrl>#hv_330kv</styleUrl>"));
+        assert!(kml.contains("<name>11kV line b</name><styleUrl>#hv_other</styleUrl>"));
+        // higher voltages first
+        assert!(kml.find("330kV lines").unwrap() < kml.find("11kV lines").unwrap());
+    }
+
+    #[test]
+    fn connector_labelled_with_distance() {
+        let kml = report_to_kml(&report(), &details());
+        assert!(kml.contains("<name>120m to 330kV</name>"));
+        assert!(kml.contains("<name>13m to 11kV</name>"));
+        let mut report = report();
+        report.distances[0].query_position = Point { x: 0.0, y: 5.0 }.to_geo_position();
+        let kml = report_to_kml(&report, &details());
+        assert!(kml.contains(&coordinates(
+            [
+                &report.distances[0].query_position,
+                &report.distances[0].nearest_position
+            ]
+            .into_iter()
+        )));
+    }
+
+    #[test]
+    fn range_rings_closed() {
+        let kml = report_to_kml(&report(), &details());
+        for radius in RANGE_RINGS_M {
+            let placemark = kml
+                .split("<Placemark>")
+                .find(|p| p.starts_with(&format!("<name>{}m</name>", radius)))
+                .unwrap();
+            let coordinates = placemark
+                .split("<coordinates>")
+                .nth(1)
+                .unwrap()
+                .split("</coordinates>")
+                .next()
+                .unwrap()
+                .split(' ')
+                .collect::<Vec<&str>>();
+            assert_eq!(coordinates.len(), 65);
+            assert_eq!(coordinates.first(), coordinates.last());
+        }
+    }
+
+    #[test]
+    fn name_escaped() {
+        let kml = report_to_kml(&report(), &details());
+        assert!(kml.contains("<name>1 Smith &amp; Sons St</name>"));
+        assert!(!kml.contains("Smith & Sons"));
+    }
+
+    #[test]
+    fn export_kmz_zipped() {
+        let path = std::env::temp_dir().join(format!("hv-distance-{}.kmz", std::process::id()));
+        export_kml(&path, &report(), &details()).unwrap();
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        let mut kml = String::new();
+        archive
+            .by_name("doc.kml")
+            .unwrap()
+            .read_to_string(&mut kml)
+            .unwrap();
+        assert_eq!(kml, report_to_kml(&report(), &details()));
+        fs::remove_file(&path).unwrap();
+    }
+}
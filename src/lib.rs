@@ -0,0 +1,25 @@
+#[macro_use]
+extern crate log;
+
+pub mod api;
+pub mod batch;
+pub mod data_source;
+pub mod disambiguation;
+pub mod error;
+pub mod geometry;
+pub mod html;
+pub mod kml;
+pub mod lot;
+pub mod output;
+pub mod policy;
+pub mod query;
+pub mod style;
+pub mod vtk;
+
+pub use api::geocode::{Geocoder, GeocoderConfig};
+pub use error::HvError;
+pub use output::OutputFormat;
+pub use policy::ClearancePolicy;
+pub use query::{
+    CachePolicy, DistanceQuery, DistanceReport, QueryLocation, QuerySession, VoltageDistance,
+};
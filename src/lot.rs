@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::error::{HvError, Result};
+use crate::geometry::GeoPosition;
+
+// The outer ring of a lot, from a GeoJSON Polygon (alone, as a Feature, or the first one in a
+// FeatureCollection) or a WKT POLYGON. Holes are ignored, a line over a hole still crosses the lot.
+pub fn parse_lot(text: &str) -> Result<Vec<GeoPosition>> {
+    let text = text.trim();
+    let mut ring = if text.starts_with('{') {
+        parse_geojson(text)
+    } else {
+        parse_wkt(text)
+    }
+    .map_err(HvError::MalformedLot)?;
+    // repeated points, including the closing one, are not spans of the boundary
+    let same = |a: &GeoPosition, b: &GeoPosition| {
+        a.latitude_degree() == b.latitude_degree() && a.longitude_degree() == b.longitude_degree()
+    };
+    ring.dedup_by(|a, b| same(a, b));
+    if ring.len() > 1 && same(&ring[0], &ring[ring.len() - 1]) {
+        ring.pop();
+    }
+    if ring.len() < 3 {
+        return Err(HvError::MalformedLot(format!(
+            "a lot needs at least 3 points, got {}",
+            ring.len()
+        )));
+    }
+    Ok(ring)
+}
+
+pub fn load_lot(file_path: &Path) -> Result<Vec<GeoPosition>> {
+    let text = fs::read_to_string(file_path)
+        .map_err(|e| HvError::MalformedLot(format!("'{}': {}", file_path.display(), e)))?;
+    parse_lot(&text)
+}
+
+// A lot as WKT, e.g. to be shown as the input of a query.
+pub fn to_wkt(ring: &[GeoPosition]) -> String {
+    let mut points = ring
+        .iter()
+        .map(|p| format!("{} {}", p.longitude_degree(), p.latitude_degree()))
+        .collect::<Vec<String>>();
+    if points.first() != points.last() {
+        points.push(points[0].to_owned());
+    }
+    format!("POLYGON(({}))", points.join(", "))
+}
+
+fn to_position(
+    longitude_degree: f64,
+    latitude_degree: f64,
+) -> std::result::Result<GeoPosition, String> {
+    if !(-90.0..=90.0).contains(&latitude_degree) || !(-180.0..=180.0).contains(&longitude_degree) {
+        return Err(format!(
+            "'{} {}' is not a longitude and latitude in degree",
+            longitude_degree, latitude_degree
+        ));
+    }
+    Ok(GeoPosition::from_degree(latitude_degree, longitude_degree))
+}
+
+fn find_polygon(value: &Value) -> Option<&Value> {
+    match value["type"].as_str()? {
+        "Polygon" => Some(value),
+        "Feature" => find_polygon(&value["geometry"]),
+        "FeatureCollection" => value["features"].as_array()?.iter().find_map(find_polygon),
+        _ => None,
+    }
+}
+
+fn parse_geojson(text: &str) -> std::result::Result<Vec<GeoPosition>, String> {
+    let value = serde_json::from_str::<Value>(text).map_err(|e| e.to_string())?;
+    let polygon = find_polygon(&value).ok_or("no Polygon found in the GeoJSON")?;
+    let ring = polygon["coordinates"][0]
+        .as_array()
+        .ok_or("a Polygon needs an array of rings as its coordinates")?;
+    ring.iter()
+        .map(|point| match point.as_array().map(|p| p.as_slice()) {
+            Some([Value::Number(longitude), Value::Number(latitude), ..]) => to_position(
+                longitude.as_f64().unwrap_or(f64::NAN),
+                latitude.as_f64().unwrap_or(f64::NAN),
+            ),
+            _ => Err(format!("expect [longitude, latitude] but got '{}'", point)),
+        })
+        .collect()
+}
+
+fn parse_wkt(text: &str) -> std::result::Result<Vec<GeoPosition>, String> {
+    let upper = text.to_uppercase();
+    let body = upper
+        .strip_prefix("POLYGON")
+        .ok_or_else(|| format!("expect a GeoJSON or a WKT POLYGON but got '{}'", text))?
+        .trim();
+    let body = body
+        .strip_prefix('(')
+        .and_then(|b| b.strip_suffix(')'))
+        .ok_or("expect POLYGON((lon lat, ...))")?;
+    // the outer ring is the first one
+    let ring = body
+        .trim()
+        .strip_prefix('(')
+        .and_then(|b| b.split(')').next())
+        .ok_or("expect POLYGON((lon lat, ...))")?;
+    ring.split(',')
+        .map(|point| {
+            let numbers = point
+                .split_whitespace()
+                .map(|n| n.parse::<f64>())
+                .collect::<std::result::Result<Vec<f64>, _>>()
+                .map_err(|e| format!("failed to parse '{}': {}", point.trim(), e))?;
+            match numbers.as_slice() {
+                [longitude, latitude, ..] => to_position(*longitude, *latitude),
+                _ => Err(format!("expect 'lon lat' but got '{}'", point.trim())),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_ring(ring: &[GeoPosition]) {
+        assert_eq!(ring.len(), 4);
+        assert!((ring[0].latitude_degree() + 33.7565).abs() < 1e-9);
+        assert!((ring[0].longitude_degree() - 151.2286).abs() < 1e-9);
+        assert!((ring[2].latitude_degree() + 33.7568).abs() < 1e-9);
+    }
+
+    #[test]
+    fn geojson_polygon() {
+        let polygon = r#"{"type": "Polygon", "coordinates": [
+            [[151.2286, -33.7565], [151.2289, -33.7565], [151.2289, -33.7568], [151.2286, -33.7568], [151.2286, -33.7565]],
+            [[151.2287, -33.7566], [151.2288, -33.7566], [151.2288, -33.7567], [151.2287, -33.7566]]
+        ]}"#;
+        assert_ring(&parse_lot(polygon).unwrap());
+        let feature = format!(
+            r#"{{"type": "FeatureCollection", "features": [
+                {{"type": "Feature", "geometry": {{"type": "Point", "coordinates": [151.0, -33.0]}}}},
+                {{"type": "Feature", "properties": {{"lot": "12/DP345"}}, "geometry": {}}}
+            ]}}"#,
+            polygon
+        );
+        assert_ring(&parse_lot(&feature).unwrap());
+    }
+
+    #[test]
+    fn wkt_polygon() {
+        let wkt = "POLYGON ((151.2286 -33.7565, 151.2289 -33.7565, 151.2289 -33.7568, 151.2286 -33.7568, 151.2286 -33.7565), (151.2287 -33.7566, 151.2288 -33.7566, 151.2288 -33.7567, 151.2287 -33.7566))";
+        let ring = parse_lot(wkt).unwrap();
+        assert_ring(&ring);
+        assert_ring(&parse_lot(&to_wkt(&ring)).unwrap());
+        assert_ring(&parse_lot(&wkt.to_lowercase()).unwrap());
+    }
+
+    #[test]
+    fn malformed() {
+        for text in [
+            "POINT (151.2 -33.7)",
+            "POLYGON ((151.2 -33.7, 151.3 -33.7))",
+            "POLYGON ((151.2 -33.7, 151.3 -33.7, 151.3 -33.7, 151.2 -33.7))",
+            "POLYGON ((151.2 -33.7, 151.3 abc, 151.3 -33.8))",
+            // latitude and longitude swapped
+            "POLYGON ((-33.7 151.2, -33.7 151.3, -33.8 151.3))",
+            r#"{"type": "LineString", "coordinates": [[151.2, -33.7], [151.3, -33.7]]}"#,
+            r#"{"type": "Polygon", "coordinates": [[[151.2], [151.3, -33.7], [151.3, -33.8]]]}"#,
+        ] {
+            assert!(
+                matches!(parse_lot(text), Err(HvError::MalformedLot(_))),
+                "{}",
+                text
+            );
+        }
+    }
+}
@@ -1,31 +1,193 @@
 #[macro_use]
 extern crate log;
 extern crate simplelog;
-use clap::Parser;
-use data_source::{HighVoltageLine, SuburbInfo};
-use simplelog::{ColorChoice, ConfigBuilder, LevelFilter, TermLogger, TerminalMode};
-use std::{
-    collections::{HashMap, HashSet},
-    path,
+use clap::{ArgGroup, Parser, Subcommand};
+use hv_distance::api::geocode::{
+    GeocoderConfig, GeocoderKind, DEFAULT_MIN_CONFIDENCE, NOMINATIM_URL,
 };
-
-mod api;
-mod data_source;
-mod geometry;
+use hv_distance::batch::{self, BatchFormat};
+use hv_distance::disambiguation::{AddressCandidate, Disambiguation};
+use hv_distance::geometry::{DistanceModel, GeoPosition, ProjectionMethod};
+use hv_distance::query::{ProjectionOrigin, DEFAULT_MAX_SEARCH_RADIUS_M};
+use hv_distance::vtk::VtkFormat;
+use hv_distance::{
+    html, kml, lot, output, vtk, CachePolicy, ClearancePolicy, DistanceQuery, DistanceReport,
+    HvError, OutputFormat, QuerySession,
+};
+use simplelog::{ColorChoice, ConfigBuilder, LevelFilter, TermLogger, TerminalMode};
+use std::rc::Rc;
+use std::{fs, io, path};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("location").required(true).args(["address", "lat", "point", "lot"])))]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(short, long)]
-    address: String,
+    address: Option<String>,
+
+    #[arg(long, requires = "lon", allow_negative_numbers = true)]
+    lat: Option<f64>,
+
+    #[arg(long, requires = "lat", allow_negative_numbers = true)]
+    lon: Option<f64>,
+
+    /// "lat,lon" in degree, e.g. "-33.7565,151.2286"
+    #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+    point: Option<(f64, f64)>,
+
+    /// a GeoJSON Polygon or a WKT POLYGON in lon/lat degree, distances are from any part of the lot
+    #[arg(long)]
+    lot: Option<path::PathBuf>,
+
+    /// "lat,lon" in degree where distances are calculated around, default to the queried location
+    #[arg(long, value_parser = parse_point, allow_hyphen_values = true, global = true)]
+    projection_origin: Option<(f64, f64)>,
+
+    #[arg(long, value_enum, default_value_t = ProjectionMethod::Sinusoidal, global = true)]
+    projection: ProjectionMethod,
+
+    /// planar measures on the projected plane, spherical and ellipsoidal measure on the earth
+    #[arg(long, value_enum, default_value_t = DistanceModel::Planar, global = true)]
+    distance_model: DistanceModel,
+
+    /// write the suburbs, lines and the query to this directory as .vtk files, e.g. for ParaView
+    #[arg(long)]
+    export_vtk: Option<path::PathBuf>,
+
+    /// write the query, the lines and the range rings for Google Earth, zipped when it ends with .kmz
+    #[arg(long)]
+    export_kml: Option<path::PathBuf>,
 
-    #[arg(short, long, default_value_t = false)]
+    /// write a single html file with a map of the result and a table of the distances
+    #[arg(long)]
+    report: Option<path::PathBuf>,
+
+    /// legacy ascii or binary .vtk, or xml .vtp
+    #[arg(long, value_enum, default_value_t = VtkFormat::Ascii, requires = "export_vtk")]
+    vtk_format: VtkFormat,
+
+    /// text is printed as log, others are printed to stdout
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// stop expanding the search to suburbs further than this, in meter
+    #[arg(long, default_value_t = DEFAULT_MAX_SEARCH_RADIUS_M, global = true)]
+    max_radius: f64,
+
+    /// where addresses are looked up, gazetteer looks up a local csv without any request
+    #[arg(long, value_enum, default_value_t = GeocoderKind::MapsCo, global = true)]
+    geocoder: GeocoderKind,
+
+    /// api key for geocode.maps.co
+    #[arg(long, global = true)]
+    geocoder_key: Option<String>,
+
+    /// nominatim server to use
+    #[arg(long, default_value = NOMINATIM_URL, global = true)]
+    geocoder_url: String,
+
+    /// email sent to nominatim, as asked by its usage policy
+    #[arg(long, global = true)]
+    geocoder_email: Option<String>,
+
+    /// csv with `address`, `lat` and `lon` columns, e.g. an extract of G-NAF
+    #[arg(long, required_if_eq("geocoder", "gazetteer"), global = true)]
+    gazetteer: Option<path::PathBuf>,
+
+    /// addresses in the gazetteer less alike than this, from 0 to 1, are not taken
+    #[arg(long, default_value_t = DEFAULT_MIN_CONFIDENCE, global = true)]
+    gazetteer_min_confidence: f64,
+
+    /// list the places found for the address and ask which one to use
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "strict",
+        global = true
+    )]
+    pick: bool,
+
+    /// fail instead of guessing when more than one place is found for the address
+    #[arg(long, default_value_t = false, global = true)]
+    strict: bool,
+
+    /// .toml or .json with the clearance bands per voltage, the one shipped with the crate if not given
+    #[arg(long, global = true)]
+    policy: Option<path::PathBuf>,
+
+    #[arg(short, long, default_value_t = false, global = true)]
     verbose: bool,
 
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, global = true)]
     no_cache: bool,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compute distances for every row of a .csv or .jsonl file.
+    /// Each row needs either an `address`, or `lat` and `lon`, with an optional `id`.
+    Batch {
+        #[arg(short, long)]
+        input: path::PathBuf,
+
+        /// .csv or .jsonl, print csv to stdout if not given
+        #[arg(short, long)]
+        output: Option<path::PathBuf>,
+    },
+    /// List every line within a radius of the location, with the length of line inside it.
+    #[command(group(ArgGroup::new("location").required(true).args(["address", "lat", "point"])))]
+    Nearby {
+        /// in meter
+        #[arg(long, value_parser = parse_radius)]
+        radius: f64,
+
+        #[arg(short, long)]
+        address: Option<String>,
+
+        #[arg(long, requires = "lon", allow_negative_numbers = true)]
+        lat: Option<f64>,
+
+        #[arg(long, requires = "lat", allow_negative_numbers = true)]
+        lon: Option<f64>,
+
+        /// "lat,lon" in degree, e.g. "-33.7565,151.2286"
+        #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+        point: Option<(f64, f64)>,
+
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+fn parse_point(s: &str) -> Result<(f64, f64), String> {
+    let parts = s.split(',').map(|p| p.trim()).collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        return Err(format!("expect 'lat,lon' but got '{}'", s));
+    }
+    let latitude = parts[0]
+        .parse::<f64>()
+        .map_err(|e| format!("failed to parse latitude '{}': {}", parts[0], e))?;
+    let longitude = parts[1]
+        .parse::<f64>()
+        .map_err(|e| format!("failed to parse longitude '{}': {}", parts[1], e))?;
+    Ok((latitude, longitude))
+}
+
+fn parse_radius(s: &str) -> Result<f64, String> {
+    let radius = s
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("failed to parse radius '{}': {}", s, e))?;
+    if !radius.is_finite() || radius <= 0.0 {
+        return Err(format!("expect a positive radius in meter but got '{}'", s));
+    }
+    Ok(radius)
+}
+
 fn init_logger(verbose: bool) {
     let log_level = if verbose {
         LevelFilter::Trace
@@ -39,103 +201,338 @@ fn init_logger(verbose: bool) {
         .expect("failed to init logger");
 }
 
-fn print_results(distances: &HashMap<u16, f64>, mut voltages: Vec<u16>) {
-    if distances.is_empty() {
-        info!("no high voltage power line found in this area.");
+// the text output has no nearest point, it is logged with `-v`
+fn log_nearest_points(report: &DistanceReport) {
+    for distance in report.distances.iter() {
+        debug!(
+            "nearest point is on segment {} of line '{}' at {:.6}, {:.6}",
+            distance.segment_index,
+            distance.line_id,
+            distance.nearest_position.latitude_degree(),
+            distance.nearest_position.longitude_degree()
+        );
     }
-    let mut min_distance = f64::INFINITY;
-    voltages.sort_by(|a, b| b.cmp(a));
-    for voltage in voltages {
-        let distance = *distances.get(&voltage).unwrap();
-        if distance < min_distance {
-            min_distance = distance;
-            info!("{:.0}m away from {}kV power line", distance, voltage);
+}
+
+fn geocoder_config(args: &Args) -> GeocoderConfig {
+    match args.geocoder {
+        GeocoderKind::MapsCo => GeocoderConfig::MapsCo {
+            api_key: args.geocoder_key.clone(),
+        },
+        GeocoderKind::Nominatim => GeocoderConfig::Nominatim {
+            url: args.geocoder_url.to_owned(),
+            email: args.geocoder_email.clone(),
+        },
+        GeocoderKind::Gazetteer => GeocoderConfig::Gazetteer {
+            file_path: args
+                .gazetteer
+                .clone()
+                .expect("clap requires --gazetteer for the gazetteer geocoder"),
+            min_confidence: args.gazetteer_min_confidence,
+        },
+    }
+}
+
+// Candidates are listed on stderr, so that stdout only has the result.
+fn pick_candidate(address: &str, candidates: &[AddressCandidate]) -> Option<usize> {
+    eprintln!("more than one place found for '{}':", address);
+    for (idx, candidate) in candidates.iter().enumerate() {
+        eprintln!(
+            "  [{}] {} (suburb: {})",
+            idx + 1,
+            candidate.address.full_address,
+            candidate.suburb.as_deref().unwrap_or("unknown")
+        );
+    }
+    eprint!("which one? [1-{}, empty to give up]: ", candidates.len());
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok()?;
+    answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+}
+
+fn disambiguation(args: &Args) -> Disambiguation {
+    if args.pick {
+        Disambiguation::Pick(Rc::new(pick_candidate))
+    } else if args.strict {
+        Disambiguation::Strict
+    } else {
+        Disambiguation::Best
+    }
+}
+
+fn policy(args: &Args) -> Result<ClearancePolicy, HvError> {
+    match &args.policy {
+        Some(file_path) => ClearancePolicy::load(file_path),
+        None => Ok(ClearancePolicy::default()),
+    }
+}
+
+// The settings shared by every kind of query, from the global args.
+fn configure(args: &Args, policy: ClearancePolicy) -> impl Fn(DistanceQuery) -> DistanceQuery + '_ {
+    let projection_origin = match args.projection_origin {
+        Some((latitude, longitude)) => {
+            ProjectionOrigin::Fixed(GeoPosition::from_degree(latitude, longitude))
         }
+        None => ProjectionOrigin::Query,
+    };
+    move |query| {
+        query
+            .max_search_radius(args.max_radius)
+            .projection_origin(projection_origin.clone())
+            .projection_method(args.projection.clone())
+            .distance_model(args.distance_model.clone())
+            .policy(policy.clone())
     }
 }
 
-fn filter_suburb(
-    place: &geometry::Point,
-    suburbs: Vec<SuburbInfo>,
-    range_m: f64,
-) -> Vec<SuburbInfo> {
-    suburbs
-        .into_iter()
-        .filter(|s| s.location.distance_to(&place) < range_m)
-        .collect()
-}
-
-fn aggregate_high_voltage_lines(
-    mut acc: HashMap<u16, Vec<HighVoltageLine>>,
-    map: HashMap<u16, Vec<HighVoltageLine>>,
-    cached_line_id: &mut HashSet<String>,
-) -> HashMap<u16, Vec<HighVoltageLine>> {
-    {
-        for (k, v) in map {
-            let mut lines = Vec::<HighVoltageLine>::new();
-            for line in v {
-                if cached_line_id.contains(&line.id) {
-                    continue;
-                }
-                cached_line_id.insert(line.id.to_owned());
-                lines.push(line);
-            }
-            match acc.get_mut(&k) {
-                Some(existing_lines) => {
-                    existing_lines.extend(lines);
-                }
-                None => {
-                    acc.insert(k, lines);
-                }
-            }
+fn exit_code(e: &HvError) -> i32 {
+    // 1 is left for generic failures and 2 is used by clap for usage errors.
+    match e {
+        HvError::GeocodeNotFound(_) => 3,
+        HvError::UpstreamHttp { .. } => 4,
+        HvError::MalformedResponse { .. } => 5,
+        HvError::OutOfCoverage { .. } => 12,
+        HvError::MalformedSuburbRecord { .. } => 6,
+        HvError::UnsupportedGeometry(_) => 7,
+        HvError::MissingVoltage(_) => 8,
+        HvError::MultipleVoltages { .. } => 9,
+        HvError::InvalidVoltage(_) => 10,
+        HvError::CacheIo { .. } => 11,
+        HvError::UnsupportedBatchFormat(_) => 13,
+        HvError::MalformedBatchRecord { .. } => 14,
+        HvError::BatchIo { .. } => 15,
+        HvError::BatchOutput(_) => 16,
+        HvError::ReportOutput(_) => 17,
+        HvError::VtkExport { .. } => 18,
+        HvError::KmlExport { .. } => 19,
+        HvError::Gazetteer { .. } => 20,
+        HvError::AmbiguousAddress { .. } => 21,
+        HvError::Policy { .. } => 22,
+        HvError::MalformedLot(_) => 23,
+        HvError::InvalidGeometry { .. } => 24,
+    }
+}
+
+fn run_single(args: &Args, cache_policy: CachePolicy) -> Result<(), HvError> {
+    let configure = configure(args, policy(args)?);
+    let query = match (&args.address, args.lat, args.lon, args.point, &args.lot) {
+        (Some(address), _, _, _, _) => DistanceQuery::from_address(address),
+        (_, Some(latitude), Some(longitude), _, _) | (_, _, _, Some((latitude, longitude)), _) => {
+            DistanceQuery::from_coordinates(latitude, longitude)
         }
-        acc
+        (_, _, _, _, Some(file_path)) => DistanceQuery::from_lot(lot::load_lot(file_path)?),
+        _ => unreachable!("clap requires one of --address, --lat/--lon, --point or --lot"),
+    };
+    let query = configure(query)
+        .cache_policy(cache_policy)
+        .geocoder(geocoder_config(args))
+        .disambiguation(disambiguation(args));
+    let detailed = args.export_vtk.is_some() || args.export_kml.is_some() || args.report.is_some();
+    let report = if detailed {
+        let (report, details) = query.run_detailed()?;
+        if let Some(dir) = &args.export_vtk {
+            vtk::export_suburb_to_vtk(dir, &report, &details, &args.vtk_format)?;
+            info!("vtk files written to '{}'", dir.display());
+        }
+        if let Some(path) = &args.export_kml {
+            kml::export_kml(path, &report, &details)?;
+            info!("kml written to '{}'", path.display());
+        }
+        if let Some(path) = &args.report {
+            html::write_html_report(path, &report, &details)?;
+            info!("report written to '{}'", path.display());
+        }
+        report
+    } else {
+        query.run()?
+    };
+    if args.format == OutputFormat::Text {
+        log_nearest_points(&report);
+    }
+    output::write_report(io::stdout().lock(), &args.format, &report)
+}
+
+fn run_nearby(args: &Args, cache_policy: CachePolicy) -> Result<(), HvError> {
+    let Some(Command::Nearby {
+        radius,
+        address,
+        lat,
+        lon,
+        point,
+        format,
+    }) = &args.command
+    else {
+        unreachable!("only called for the nearby command");
+    };
+    let query = match (address, lat, lon, point) {
+        (Some(address), _, _, _) => DistanceQuery::from_address(address),
+        (_, Some(latitude), Some(longitude), _) | (_, _, _, Some((latitude, longitude))) => {
+            DistanceQuery::from_coordinates(*latitude, *longitude)
+        }
+        _ => unreachable!("clap requires one of --address, --lat/--lon or --point"),
+    };
+    let report = configure(args, policy(args)?)(query)
+        .cache_policy(cache_policy)
+        .geocoder(geocoder_config(args))
+        .disambiguation(disambiguation(args))
+        .run_nearby(*radius)?;
+    info!(
+        "{} lines within {:.0}m, {} suburbs searched",
+        report.lines.len(),
+        report.radius_m,
+        report.suburbs_searched.len()
+    );
+    output::write_nearby_report(io::stdout().lock(), format, &report)
+}
+
+fn run_batch(
+    input: &path::Path,
+    output: Option<&path::Path>,
+    cache_policy: CachePolicy,
+    geocoder: GeocoderConfig,
+    disambiguation: Disambiguation,
+    configure: impl Fn(DistanceQuery) -> DistanceQuery,
+) -> Result<(), HvError> {
+    let input_format = BatchFormat::from_path(input)?;
+    let output_format = match output {
+        Some(output) => BatchFormat::from_path(output)?,
+        None => BatchFormat::Csv,
+    };
+    let input_file = fs::File::open(input).map_err(|source| HvError::BatchIo {
+        path: input.to_path_buf(),
+        source,
+    })?;
+    let rows = batch::read_rows(input_file, &input_format);
+    info!("{} rows read from '{}'", rows.len(), input.display());
+
+    let mut session = QuerySession::new(&cache_policy)?
+        .with_geocoder(geocoder.build()?)
+        .with_disambiguation(disambiguation);
+    let results = batch::run_batch(&mut session, rows, configure);
+    let failed = results.iter().filter(|r| r.report.is_err()).count();
+    info!("{} rows processed, {} failed", results.len(), failed);
+
+    match output {
+        Some(output) => {
+            let output_file = fs::File::create(output).map_err(|source| HvError::BatchIo {
+                path: output.to_path_buf(),
+                source,
+            })?;
+            batch::write_results(io::BufWriter::new(output_file), &output_format, &results)
+        }
+        None => batch::write_results(io::stdout().lock(), &output_format, &results),
     }
 }
 
 fn main() {
     let args: Args = Args::parse();
     init_logger(args.verbose);
-    let cache = api::cache::Cache::new(
-        path::PathBuf::from("./cache/api_cache.json"),
-        !args.no_cache,
-    );
+    let cache_policy = if args.no_cache {
+        CachePolicy::Disabled
+    } else {
+        CachePolicy::default()
+    };
+    let result = match &args.command {
+        Some(Command::Batch { input, output }) => policy(&args).and_then(|policy| {
+            if args.projection_origin.is_none() {
+                info!("no --projection-origin, the lines of each suburb are parsed for every row");
+            }
+            run_batch(
+                input,
+                output.as_deref(),
+                cache_policy,
+                geocoder_config(&args),
+                disambiguation(&args),
+                configure(&args, policy),
+            )
+        }),
+        Some(Command::Nearby { .. }) => run_nearby(&args, cache_policy),
+        None => run_single(&args, cache_policy),
+    };
+    if let Err(e) = result {
+        error!("{}", e);
+        std::process::exit(exit_code(&e));
+    }
+}
 
-    let raw_suburb_map = api::property_data_map::server_init_init(&cache);
-    let address = api::geocode::find_address(&args.address);
-    let location = data_source::parse_address(address);
-    let suburbs_info: Vec<SuburbInfo> = data_source::get_all_suburbs(raw_suburb_map);
-    debug!("postcode_to_suburb_id calculated");
-
-    let filtered_suburb_infos = filter_suburb(&location, suburbs_info, 5_000.0);
-    let surrounding_suburbs_name = filtered_suburb_infos
-        .iter()
-        .map(|s| s.name.to_owned())
-        .collect::<Vec<String>>();
-    debug!(
-        "suburbs within 5km filtered: {:?}",
-        surrounding_suburbs_name
-    );
+#[cfg(test)]
+mod test_parse_point {
+    use super::*;
+
+    #[test]
+    fn success() {
+        assert_eq!(parse_point("-33.7565, 151.2286"), Ok((-33.7565, 151.2286)));
+    }
+
+    #[test]
+    fn failed_with_one_value() {
+        assert!(parse_point("-33.7565").is_err());
+    }
+
+    #[test]
+    fn failed_with_non_number() {
+        assert!(parse_point("-33.7565,abc").is_err());
+    }
+}
 
-    let mut cached_line_id = HashSet::<String>::new();
-    let high_voltage_lines = filtered_suburb_infos
-        .iter()
-        .map(|s| api::property_data_map::select_suburb(s.id, &s.name, &cache))
-        .map(|raw| data_source::parse_high_voltage_lines(raw))
-        .fold(HashMap::<u16, Vec<HighVoltageLine>>::new(), |acc, map| {
-            aggregate_high_voltage_lines(acc, map, &mut cached_line_id)
-        });
-    debug!("suburb info parsed");
-    // voltage -> distance
-    let mut distances = HashMap::<u16, f64>::new();
-    let mut voltages = Vec::<u16>::new();
-    for (voltage, lines) in high_voltage_lines {
-        let distance = lines.iter().fold(f64::INFINITY, |acc, l| {
-            acc.min(l.line.distance_to(&location))
-        });
-        distances.insert(voltage, distance);
-        voltages.push(voltage);
-    }
-    debug!("distances found {:?}", distances);
-    print_results(&distances, voltages);
+#[cfg(test)]
+mod test_parse_radius {
+    use super::*;
+
+    #[test]
+    fn success() {
+        assert_eq!(parse_radius("500"), Ok(500.0));
+    }
+
+    #[test]
+    fn failed_with_non_positive() {
+        assert!(parse_radius("0").is_err());
+        assert!(parse_radius("-10").is_err());
+    }
+
+    #[test]
+    fn failed_with_non_finite() {
+        assert!(parse_radius("inf").is_err());
+        assert!(parse_radius("NaN").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_args {
+    use super::*;
+
+    #[test]
+    fn global_args_for_subcommands() {
+        for command in [
+            vec!["nearby", "--radius", "500", "--point", "-33.7565,151.2286"],
+            vec!["batch", "--input", "rows.csv"],
+        ] {
+            let args = Args::try_parse_from(["hv-distance"].into_iter().chain(command).chain([
+                "--distance-model",
+                "ellipsoidal",
+                "--projection",
+                "equirectangular",
+            ]))
+            .unwrap();
+            assert_eq!(args.distance_model, DistanceModel::Ellipsoidal);
+            assert_eq!(args.projection, ProjectionMethod::Equirectangular);
+        }
+    }
+
+    #[test]
+    fn nearby_rejects_invalid_radius() {
+        assert!(Args::try_parse_from([
+            "hv-distance",
+            "nearby",
+            "--radius",
+            "-5",
+            "--point",
+            "-33.7565,151.2286"
+        ])
+        .is_err());
+    }
 }
@@ -0,0 +1,583 @@
+use std::io::Write;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::{HvError, Result};
+use crate::geometry::{GeoPosition, Point};
+use crate::query::{DistanceReport, NearbyReport, VoltageDistance};
+
+#[derive(Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+    Geojson,
+}
+
+#[derive(Serialize)]
+struct LatLon {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl From<&GeoPosition> for LatLon {
+    fn from(position: &GeoPosition) -> Self {
+        LatLon {
+            latitude: position.latitude_degree(),
+            longitude: position.longitude_degree(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonVoltageDistance<'a> {
+    voltage_kv: u16,
+    distance_m: f64,
+    line_id: &'a str,
+    nearest_point: LatLon,
+    segment_index: usize,
+    distance_along_m: f64,
+    band: String,
+    crosses_lot: bool,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    address: Option<&'a str>,
+    location: LatLon,
+    suburb: Option<&'a str>,
+    suburbs_searched: &'a [String],
+    searched_radius_m: f64,
+    distances: Vec<JsonVoltageDistance<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonNearbyLine<'a> {
+    line_id: &'a str,
+    voltage_kv: u16,
+    suburb: &'a str,
+    distance_m: f64,
+    length_within_m: f64,
+}
+
+#[derive(Serialize)]
+struct JsonNearbyReport<'a> {
+    address: Option<&'a str>,
+    location: LatLon,
+    radius_m: f64,
+    suburbs_searched: &'a [String],
+    lines: Vec<JsonNearbyLine<'a>>,
+}
+
+pub fn write_report<W: Write>(
+    writer: W,
+    format: &OutputFormat,
+    report: &DistanceReport,
+) -> Result<()> {
+    match format {
+        OutputFormat::Text => write_text(writer, report).map_err(|e| e.to_string()),
+        OutputFormat::Json => write_json(writer, report).map_err(|e| e.to_string()),
+        OutputFormat::Csv => write_csv(writer, report).map_err(|e| e.to_string()),
+        OutputFormat::Geojson => write_geojson(writer, report).map_err(|e| e.to_string()),
+    }
+    .map_err(HvError::ReportOutput)
+}
+
+pub fn write_nearby_report<W: Write>(
+    writer: W,
+    format: &OutputFormat,
+    report: &NearbyReport,
+) -> Result<()> {
+    match format {
+        OutputFormat::Text => write_nearby_text(writer, report).map_err(|e| e.to_string()),
+        OutputFormat::Json => write_nearby_json(writer, report).map_err(|e| e.to_string()),
+        OutputFormat::Csv => write_nearby_csv(writer, report).map_err(|e| e.to_string()),
+        OutputFormat::Geojson => write_nearby_geojson(writer, report).map_err(|e| e.to_string()),
+    }
+    .map_err(HvError::ReportOutput)
+}
+
+fn write_text<W: Write>(mut writer: W, report: &DistanceReport) -> std::io::Result<()> {
+    if report.distances.is_empty() {
+        writeln!(writer, "no high voltage power line found in this area.")?;
+    }
+    if let Some(suburb) = &report.suburb {
+        writeln!(writer, "located in {}", suburb)?;
+    }
+    writeln!(
+        writer,
+        "searched {} suburbs within {:.1}km",
+        report.suburbs_searched.len(),
+        report.searched_radius_m / 1000.0
+    )?;
+    for distance in report.decreasing_distances() {
+        writeln!(
+            writer,
+            "{:.0}m away from {}kV power line, {}{}",
+            distance.distance_m,
+            distance.voltage,
+            distance.band,
+            if distance.crosses_lot {
+                ", crosses the lot"
+            } else {
+                ""
+            }
+        )?;
+    }
+    Ok(())
+}
+
+fn write_json<W: Write>(mut writer: W, report: &DistanceReport) -> serde_json::Result<()> {
+    let json_report = JsonReport {
+        address: report.full_address.as_deref(),
+        location: LatLon::from(&report.position),
+        suburb: report.suburb.as_deref(),
+        suburbs_searched: &report.suburbs_searched,
+        searched_radius_m: report.searched_radius_m,
+        distances: report
+            .distances
+            .iter()
+            .map(|d| JsonVoltageDistance {
+                voltage_kv: d.voltage,
+                distance_m: d.distance_m,
+                line_id: &d.line_id,
+                nearest_point: LatLon::from(&d.nearest_position),
+                segment_index: d.segment_index,
+                distance_along_m: d.distance_along_m,
+                band: d.band.to_string(),
+                crosses_lot: d.crosses_lot,
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(&mut writer, &json_report)?;
+    writeln!(writer).map_err(serde_json::Error::io)
+}
+
+fn write_csv<W: Write>(writer: W, report: &DistanceReport) -> csv::Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record([
+        "voltage_kv",
+        "distance_m",
+        "line_id",
+        "nearest_latitude",
+        "nearest_longitude",
+        "segment_index",
+        "band",
+        "crosses_lot",
+    ])?;
+    for distance in &report.distances {
+        let nearest_point = LatLon::from(&distance.nearest_position);
+        csv_writer.write_record([
+            distance.voltage.to_string(),
+            format!("{:.1}", distance.distance_m),
+            distance.line_id.to_owned(),
+            nearest_point.latitude.to_string(),
+            nearest_point.longitude.to_string(),
+            distance.segment_index.to_string(),
+            distance.band.to_string(),
+            distance.crosses_lot.to_string(),
+        ])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// geojson coordinates are [longitude, latitude]
+fn to_lon_lat(position: &GeoPosition) -> [f64; 2] {
+    [position.longitude_degree(), position.latitude_degree()]
+}
+
+fn distance_features(
+    report: &DistanceReport,
+    distance: &VoltageDistance,
+) -> [serde_json::Value; 2] {
+    let to_coordinates = |point: &Point| to_lon_lat(&report.projection.unproject(point));
+    [
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": [
+                    to_coordinates(&distance.nearest_segment.a),
+                    to_coordinates(&distance.nearest_segment.b),
+                ],
+            },
+            "properties": {
+                "kind": "nearest_segment",
+                "voltage_kv": distance.voltage,
+                "line_id": distance.line_id,
+                "segment_index": distance.segment_index,
+            },
+        }),
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": [to_lon_lat(&distance.query_position), to_lon_lat(&distance.nearest_position)],
+            },
+            "properties": {
+                "kind": "connector",
+                "voltage_kv": distance.voltage,
+                "line_id": distance.line_id,
+                "distance_m": distance.distance_m,
+                "band": distance.band.to_string(),
+                "crosses_lot": distance.crosses_lot,
+            },
+        }),
+    ]
+}
+
+fn write_geojson<W: Write>(mut writer: W, report: &DistanceReport) -> serde_json::Result<()> {
+    let mut features = vec![json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": to_lon_lat(&report.position),
+        },
+        "properties": {
+            "kind": "query",
+            "address": report.full_address,
+        },
+    })];
+    if let Some(lot) = &report.lot {
+        let mut ring = lot
+            .get_vertices()
+            .iter()
+            .map(|p| to_lon_lat(&report.projection.unproject(p)))
+            .collect::<Vec<[f64; 2]>>();
+        ring.push(ring[0]);
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [ring],
+            },
+            "properties": {
+                "kind": "lot",
+            },
+        }));
+    }
+    for distance in &report.distances {
+        features.extend(distance_features(report, distance));
+    }
+    let collection = json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    serde_json::to_writer_pretty(&mut writer, &collection)?;
+    writeln!(writer).map_err(serde_json::Error::io)
+}
+
+fn write_nearby_text<W: Write>(mut writer: W, report: &NearbyReport) -> std::io::Result<()> {
+    if report.lines.is_empty() {
+        writeln!(
+            writer,
+            "no high voltage power line found within {:.0}m.",
+            report.radius_m
+        )?;
+    }
+    for line in &report.lines {
+        writeln!(
+            writer,
+            "{}kV power line {} in {}: {:.0}m away, {:.0}m within {:.0}m",
+            line.voltage,
+            line.id,
+            line.suburb,
+            line.distance_m,
+            line.length_within_m,
+            report.radius_m
+        )?;
+    }
+    Ok(())
+}
+
+fn write_nearby_json<W: Write>(mut writer: W, report: &NearbyReport) -> serde_json::Result<()> {
+    let json_report = JsonNearbyReport {
+        address: report.full_address.as_deref(),
+        location: LatLon::from(&report.position),
+        radius_m: report.radius_m,
+        suburbs_searched: &report.suburbs_searched,
+        lines: report
+            .lines
+            .iter()
+            .map(|l| JsonNearbyLine {
+                line_id: &l.id,
+                voltage_kv: l.voltage,
+                suburb: &l.suburb,
+                distance_m: l.distance_m,
+                length_within_m: l.length_within_m,
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(&mut writer, &json_report)?;
+    writeln!(writer).map_err(serde_json::Error::io)
+}
+
+fn write_nearby_csv<W: Write>(writer: W, report: &NearbyReport) -> csv::Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record([
+        "line_id",
+        "voltage_kv",
+        "suburb",
+        "distance_m",
+        "length_within_m",
+    ])?;
+    for line in &report.lines {
+        csv_writer.write_record([
+            line.id.to_owned(),
+            line.voltage.to_string(),
+            line.suburb.to_owned(),
+            format!("{:.1}", line.distance_m),
+            format!("{:.1}", line.length_within_m),
+        ])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn write_nearby_geojson<W: Write>(mut writer: W, report: &NearbyReport) -> serde_json::Result<()> {
+    let mut features = vec![json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": to_lon_lat(&report.position),
+        },
+        "properties": {
+            "kind": "query",
+            "address": report.full_address,
+            "radius_m": report.radius_m,
+        },
+    })];
+    for line in &report.lines {
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": line.positions.iter().map(to_lon_lat).collect::<Vec<[f64; 2]>>(),
+            },
+            "properties": {
+                "kind": "line",
+                "line_id": line.id,
+                "voltage_kv": line.voltage,
+                "suburb": line.suburb,
+                "distance_m": line.distance_m,
+                "length_within_m": line.length_within_m,
+            },
+        }));
+    }
+    let collection = json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    serde_json::to_writer_pretty(&mut writer, &collection)?;
+    writeln!(writer).map_err(serde_json::Error::io)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::{Polygon, Projection};
+    use crate::query::NearbyLine;
+
+    fn report() -> DistanceReport {
+        let distance = VoltageDistance::new_for_test;
+        DistanceReport {
+            full_address: Some("somewhere".to_owned()),
+            position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            projection: Projection::default(),
+            location: Point { x: 0.0, y: 0.0 },
+            lot: None,
+            // 66kV is further than 132kV
+            distances: vec![
+                distance(330, 1884.0, "a"),
+                distance(132, 548.0, "b"),
+                distance(66, 600.0, "c"),
+            ],
+            suburb: Some("FRENCHS FOREST".to_owned()),
+            suburbs_searched: vec!["FRENCHS FOREST".to_owned()],
+            searched_radius_m: 1234.5,
+        }
+    }
+
+    fn write(format: OutputFormat) -> String {
+        let mut buf = Vec::<u8>::new();
+        write_report(&mut buf, &format, &report()).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn text_only_decreasing_distances() {
+        assert_eq!(
+            write(OutputFormat::Text),
+            "located in FRENCHS FOREST\n\
+             searched 1 suburbs within 1.2km\n\
+             1884m away from 330kV power line, outside 300m\n\
+             548m away from 132kV power line, outside 200m\n"
+        );
+    }
+
+    #[test]
+    fn json_every_voltage() {
+        let json = serde_json::from_str::<serde_json::Value>(&write(OutputFormat::Json)).unwrap();
+        assert_eq!(json["address"], "somewhere");
+        assert_eq!(json["searched_radius_m"], 1234.5);
+        assert_eq!(json["suburb"], "FRENCHS FOREST");
+        let distances = json["distances"].as_array().unwrap();
+        assert_eq!(distances.len(), 3);
+        assert_eq!(distances[2]["voltage_kv"], 66);
+        assert_eq!(distances[2]["line_id"], "c");
+        assert_eq!(distances[2]["distance_m"], 600.0);
+        assert_eq!(distances[2]["band"], "outside 150m");
+        // sydney central
+        let latitude = json["location"]["latitude"].as_f64().unwrap();
+        let longitude = json["location"]["longitude"].as_f64().unwrap();
+        assert!((latitude + 33.88243560003056).abs() < 1e-8);
+        assert!((longitude - 151.2064118987779).abs() < 1e-8);
+        let nearest_latitude = distances[2]["nearest_point"]["latitude"].as_f64().unwrap();
+        assert!(nearest_latitude > latitude);
+    }
+
+    #[test]
+    fn csv_every_voltage() {
+        let csv = write(OutputFormat::Csv);
+        let lines = csv.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines[0],
+            "voltage_kv,distance_m,line_id,nearest_latitude,nearest_longitude,segment_index,band,crosses_lot"
+        );
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("330,1884.0,a,-33.8"));
+        assert!(lines[3].starts_with("66,600.0,c,-33.8"));
+        assert!(lines[3].ends_with(",0,outside 150m,false"));
+    }
+
+    #[test]
+    fn geojson_features() {
+        let json =
+            serde_json::from_str::<serde_json::Value>(&write(OutputFormat::Geojson)).unwrap();
+        assert_eq!(json["type"], "FeatureCollection");
+        let features = json["features"].as_array().unwrap();
+        // query point, then a segment and a connector for each voltage
+        assert_eq!(features.len(), 7);
+        assert_eq!(features[0]["geometry"]["type"], "Point");
+        assert_eq!(features[1]["properties"]["kind"], "nearest_segment");
+        assert_eq!(features[2]["properties"]["kind"], "connector");
+        assert_eq!(features[2]["properties"]["distance_m"], 1884.0);
+        assert_eq!(features[2]["properties"]["band"], "outside 300m");
+        let connector = features[2]["geometry"]["coordinates"].as_array().unwrap();
+        assert_eq!(connector.len(), 2);
+        // [longitude, latitude]
+        assert!(connector[0][0].as_f64().unwrap() > 151.0);
+        assert!(connector[0][1].as_f64().unwrap() < -33.0);
+    }
+
+    fn nearby_report() -> NearbyReport {
+        let line = |id: &str, voltage: u16, distance_m: f64| NearbyLine {
+            id: id.to_owned(),
+            voltage,
+            suburb: "FRENCHS FOREST".to_owned(),
+            distance_m,
+            length_within_m: 250.0,
+            positions: vec![
+                Point {
+                    x: -10.0,
+                    y: distance_m,
+                }
+                .to_geo_position(),
+                Point {
+                    x: 10.0,
+                    y: distance_m,
+                }
+                .to_geo_position(),
+            ],
+        };
+        NearbyReport {
+            full_address: Some("somewhere".to_owned()),
+            position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            radius_m: 1000.0,
+            lines: vec![line("b", 132, 548.0), line("a", 330, 884.0)],
+            suburbs_searched: vec!["FRENCHS FOREST".to_owned()],
+        }
+    }
+
+    fn write_nearby(format: OutputFormat) -> String {
+        let mut buf = Vec::<u8>::new();
+        write_nearby_report(&mut buf, &format, &nearby_report()).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn nearby_every_line() {
+        assert_eq!(
+            write_nearby(OutputFormat::Text),
+            "132kV power line b in FRENCHS FOREST: 548m away, 250m within 1000m\n\
+             330kV power line a in FRENCHS FOREST: 884m away, 250m within 1000m\n"
+        );
+        let csv = write_nearby(OutputFormat::Csv);
+        let lines = csv.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines[0],
+            "line_id,voltage_kv,suburb,distance_m,length_within_m"
+        );
+        assert_eq!(lines[2], "a,330,FRENCHS FOREST,884.0,250.0");
+        let json =
+            serde_json::from_str::<serde_json::Value>(&write_nearby(OutputFormat::Json)).unwrap();
+        assert_eq!(json["radius_m"], 1000.0);
+        assert_eq!(json["lines"][0]["line_id"], "b");
+        assert_eq!(json["lines"][0]["length_within_m"], 250.0);
+    }
+
+    #[test]
+    fn nearby_geojson_lines() {
+        let json = serde_json::from_str::<serde_json::Value>(&write_nearby(OutputFormat::Geojson))
+            .unwrap();
+        let features = json["features"].as_array().unwrap();
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[1]["geometry"]["type"], "LineString");
+        assert_eq!(
+            features[1]["geometry"]["coordinates"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(features[2]["properties"]["voltage_kv"], 330);
+    }
+
+    #[test]
+    fn lot_crossed() {
+        let mut report = report();
+        report.lot = Some(
+            Polygon::new(vec![
+                Point { x: -10.0, y: -10.0 },
+                Point { x: 10.0, y: -10.0 },
+                Point { x: 10.0, y: 10.0 },
+            ])
+            .unwrap(),
+        );
+        report.distances[1].crosses_lot = true;
+        report.distances[1].query_point = Point { x: 10.0, y: 10.0 };
+        report.distances[1].query_position = report.distances[1].query_point.to_geo_position();
+        let mut buf = Vec::<u8>::new();
+        write_report(&mut buf, &OutputFormat::Text, &report).unwrap();
+        assert!(String::from_utf8(buf)
+            .unwrap()
+            .contains("548m away from 132kV power line, outside 200m, crosses the lot\n"));
+        let mut buf = Vec::<u8>::new();
+        write_report(&mut buf, &OutputFormat::Geojson, &report).unwrap();
+        let json = serde_json::from_slice::<serde_json::Value>(&buf).unwrap();
+        let features = json["features"].as_array().unwrap();
+        assert_eq!(features[1]["properties"]["kind"], "lot");
+        // closed
+        let ring = features[1]["geometry"]["coordinates"][0]
+            .as_array()
+            .unwrap();
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring[0], ring[3]);
+        assert_eq!(features[5]["properties"]["kind"], "connector");
+        assert_eq!(features[5]["properties"]["crosses_lot"], true);
+        // from the lot rather than the location
+        assert_eq!(features[5]["geometry"]["coordinates"][0], ring[2]);
+    }
+}
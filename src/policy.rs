@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{HvError, Result};
+
+const DEFAULT_POLICY: &str = include_str!("default_policy.toml");
+const DEFAULT_KEY: &str = "default";
+
+#[derive(Deserialize)]
+struct RawPolicy {
+    // voltage in kV, or "default" -> band widths in meter
+    bands: HashMap<String, Vec<f64>>,
+}
+
+// Where a distance falls in the clearance bands of its voltage.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Band {
+    // the narrowest band the distance is within, by its index from the narrowest and its width
+    Within { index: usize, width_m: f64 },
+    // further than the widest band
+    Outside { width_m: f64 },
+    // no band for the voltage
+    Unclassified,
+}
+
+impl Display for Band {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Band::Within { width_m, .. } => write!(f, "within {}m", width_m),
+            Band::Outside { width_m } => write!(f, "outside {}m", width_m),
+            Band::Unclassified => write!(f, "unclassified"),
+        }
+    }
+}
+
+// Clearance bands per voltage, read from toml or json as
+//   [bands]
+//   330 = [60, 150, 300]
+//   default = [10, 40, 100]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClearancePolicy {
+    // voltage in kV -> band widths in meter, narrowest first
+    bands: BTreeMap<u16, Vec<f64>>,
+    // for voltages not listed, no band when empty
+    default_bands: Vec<f64>,
+}
+
+impl Default for ClearancePolicy {
+    fn default() -> Self {
+        ClearancePolicy::from_toml(DEFAULT_POLICY).expect("the default policy is valid")
+    }
+}
+
+fn check_bands(key: &str, bands: &[f64]) -> std::result::Result<(), String> {
+    if bands.iter().any(|b| !b.is_finite() || *b <= 0.0) {
+        return Err(format!(
+            "bands of '{}' must be positive, got {:?}",
+            key, bands
+        ));
+    }
+    if bands.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(format!(
+            "bands of '{}' must be from the narrowest to the widest, got {:?}",
+            key, bands
+        ));
+    }
+    Ok(())
+}
+
+// e.g. `330`, `330kV` or `330 kv`
+fn parse_voltage(key: &str) -> std::result::Result<u16, String> {
+    let lower = key.trim().to_lowercase();
+    lower
+        .strip_suffix("kv")
+        .unwrap_or(&lower)
+        .trim()
+        .parse::<u16>()
+        .map_err(|_| format!("expect a voltage in kV or 'default', got '{}'", key))
+}
+
+impl ClearancePolicy {
+    pub fn load(file_path: &Path) -> Result<Self> {
+        let policy_error = |reason: String| HvError::Policy {
+            path: file_path.to_path_buf(),
+            reason,
+        };
+        let text = fs::read_to_string(file_path).map_err(|e| policy_error(e.to_string()))?;
+        let extension = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match extension.as_deref() {
+            Some("toml") => ClearancePolicy::from_toml(&text),
+            Some("json") => ClearancePolicy::from_json(&text),
+            _ => Err("only .toml and .json are supported".to_owned()),
+        }
+        .map_err(policy_error)
+    }
+
+    pub fn from_toml(text: &str) -> std::result::Result<Self, String> {
+        let raw = toml::from_str::<RawPolicy>(text).map_err(|e| e.to_string())?;
+        ClearancePolicy::from_raw(raw)
+    }
+
+    pub fn from_json(text: &str) -> std::result::Result<Self, String> {
+        let raw = serde_json::from_str::<RawPolicy>(text).map_err(|e| e.to_string())?;
+        ClearancePolicy::from_raw(raw)
+    }
+
+    fn from_raw(raw: RawPolicy) -> std::result::Result<Self, String> {
+        let mut bands = BTreeMap::<u16, Vec<f64>>::new();
+        let mut default_bands = None;
+        for (key, widths) in raw.bands {
+            check_bands(&key, &widths)?;
+            if key.trim().eq_ignore_ascii_case(DEFAULT_KEY) {
+                if default_bands.replace(widths).is_some() {
+                    return Err("'default' is listed more than once".to_owned());
+                }
+                continue;
+            }
+            let voltage = parse_voltage(&key)?;
+            // e.g. both `330` and `330kV`
+            if bands.insert(voltage, widths).is_some() {
+                return Err(format!("{}kV is listed more than once", voltage));
+            }
+        }
+        let default_bands = default_bands.unwrap_or_default();
+        Ok(ClearancePolicy {
+            bands,
+            default_bands,
+        })
+    }
+
+    pub fn bands_for(&self, voltage: u16) -> &[f64] {
+        self.bands.get(&voltage).unwrap_or(&self.default_bands)
+    }
+
+    pub fn classify(&self, voltage: u16, distance_m: f64) -> Band {
+        let bands = self.bands_for(voltage);
+        match bands.iter().position(|b| distance_m <= *b) {
+            Some(index) => Band::Within {
+                index,
+                width_m: bands[index],
+            },
+            None => match bands.last() {
+                Some(width_m) => Band::Outside { width_m: *width_m },
+                None => Band::Unclassified,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_policy() {
+        let policy = ClearancePolicy::default();
+        assert_eq!(policy.bands_for(330), &[60.0, 150.0, 300.0]);
+        // not listed
+        assert_eq!(policy.bands_for(11), &[10.0, 40.0, 100.0]);
+    }
+
+    #[test]
+    fn classify_narrowest_band() {
+        let policy = ClearancePolicy::default();
+        assert_eq!(
+            policy.classify(330, 45.0),
+            Band::Within {
+                index: 0,
+                width_m: 60.0
+            }
+        );
+        // the edge is within
+        assert_eq!(
+            policy.classify(330, 150.0),
+            Band::Within {
+                index: 1,
+                width_m: 150.0
+            }
+        );
+        assert_eq!(
+            policy.classify(330, 300.5),
+            Band::Outside { width_m: 300.0 }
+        );
+        assert_eq!(policy.classify(330, 120.0).to_string(), "within 150m");
+        assert_eq!(policy.classify(330, 1884.0).to_string(), "outside 300m");
+    }
+
+    #[test]
+    fn json_and_toml_alike() {
+        let toml = ClearancePolicy::from_toml("[bands]\n330 = [100]\n\"132kV\" = [50, 80]\n");
+        let json = ClearancePolicy::from_json(r#"{"bands": {"330": [100], "132kV": [50, 80]}}"#);
+        assert_eq!(toml, json);
+        let policy = toml.unwrap();
+        assert_eq!(policy.bands_for(132), &[50.0, 80.0]);
+        // nothing for voltages not listed without a default
+        assert_eq!(policy.classify(66, 10.0), Band::Unclassified);
+    }
+
+    #[test]
+    fn voltage_with_unit() {
+        let policy = ClearancePolicy::from_toml(
+            "[bands]\n\"132 kV\" = [50]\n\"66kv\" = [20]\n\" 33 \" = [10]\n",
+        )
+        .unwrap();
+        assert_eq!(policy.bands_for(132), &[50.0]);
+        assert_eq!(policy.bands_for(66), &[20.0]);
+        assert_eq!(policy.bands_for(33), &[10.0]);
+    }
+
+    #[test]
+    fn duplicated_voltage() {
+        assert!(ClearancePolicy::from_toml("[bands]\n330 = [60]\n\"330kV\" = [100]\n").is_err());
+        assert!(
+            ClearancePolicy::from_json(r#"{"bands": {"132 kV": [50], "132kv": [80]}}"#).is_err()
+        );
+        assert!(
+            ClearancePolicy::from_json(r#"{"bands": {"default": [50], "Default": [80]}}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn invalid_bands() {
+        assert!(ClearancePolicy::from_toml("[bands]\n330 = [150, 60]\n").is_err());
+        assert!(ClearancePolicy::from_toml("[bands]\n330 = [-1]\n").is_err());
+        assert!(ClearancePolicy::from_toml("[bands]\nhigh = [60]\n").is_err());
+        assert!(ClearancePolicy::from_json(r#"{"bands": {"330": "60"}}"#).is_err());
+    }
+
+    #[test]
+    fn load_by_extension() {
+        let dir = std::env::temp_dir();
+        let file_path = dir.join(format!("hv-distance-policy-{}.json", std::process::id()));
+        fs::write(&file_path, r#"{"bands": {"default": [5]}}"#).unwrap();
+        let policy = ClearancePolicy::load(&file_path).unwrap();
+        assert_eq!(policy.bands_for(330), &[5.0]);
+        fs::remove_file(&file_path).unwrap();
+        assert!(matches!(
+            ClearancePolicy::load(&file_path),
+            Err(HvError::Policy { .. })
+        ));
+        assert!(matches!(
+            ClearancePolicy::load(&dir.join("policy.yaml")),
+            Err(HvError::Policy { .. })
+        ));
+    }
+}
@@ -0,0 +1,1570 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+use std::path;
+
+use crate::api;
+use crate::api::geocode::{Geocoder, GeocoderConfig};
+use crate::api::property_data_map::SelectSuburbResponse;
+use crate::data_source::{self, HighVoltageLine, LineIndex, SuburbInfo};
+use crate::disambiguation::{self, Disambiguation};
+use crate::error::{HvError, Result};
+use crate::geometry::geodesic::{haversine_distance, vincenty_distance};
+use crate::geometry::{
+    BoundingBox, DistanceModel, GeoPosition, LineSegment, Point, Polygon, Projection,
+    ProjectionMethod,
+};
+use crate::lot;
+use crate::policy::{Band, ClearancePolicy};
+
+pub const DEFAULT_SEARCH_RADIUS_M: f64 = 5_000.0;
+pub const DEFAULT_MAX_SEARCH_RADIUS_M: f64 = 50_000.0;
+// Lines of a suburb not fetched yet are assumed within this distance from its centroid. It's a
+// heuristic, not a bound: a large rural suburb can have lines further out.
+const ASSUMED_SUBURB_RADIUS_M: f64 = 3_000.0;
+pub const DEFAULT_CACHE_FILE: &str = "./cache/api_cache.json";
+
+#[derive(Clone, Debug)]
+pub enum QueryLocation {
+    Address(String),
+    Coordinates {
+        latitude_degree: f64,
+        longitude_degree: f64,
+    },
+    // the outer ring of a lot
+    Lot(Vec<GeoPosition>),
+}
+
+impl Display for QueryLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryLocation::Address(address) => write!(f, "{}", address),
+            QueryLocation::Coordinates {
+                latitude_degree,
+                longitude_degree,
+            } => write!(f, "{},{}", latitude_degree, longitude_degree),
+            QueryLocation::Lot(ring) => write!(f, "{}", lot::to_wkt(ring)),
+        }
+    }
+}
+
+pub enum CachePolicy {
+    Enabled(path::PathBuf),
+    Disabled,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy::Enabled(path::PathBuf::from(DEFAULT_CACHE_FILE))
+    }
+}
+
+// Where the local plane used for distance calculation is tangent to the earth.
+#[derive(Clone, Debug, Default)]
+pub enum ProjectionOrigin {
+    // the query point itself, so the distortion around it is minimal.
+    #[default]
+    Query,
+    Fixed(GeoPosition),
+}
+
+pub struct DistanceQuery {
+    location: QueryLocation,
+    // suburbs within this radius are always searched
+    search_radius_m: f64,
+    // suburbs further than this are never searched
+    max_search_radius_m: f64,
+    cache_policy: CachePolicy,
+    geocoder: GeocoderConfig,
+    disambiguation: Disambiguation,
+    projection_origin: ProjectionOrigin,
+    projection_method: ProjectionMethod,
+    distance_model: DistanceModel,
+    policy: ClearancePolicy,
+}
+
+#[derive(Debug)]
+pub struct VoltageDistance {
+    pub voltage: u16,
+    pub distance_m: f64,
+    pub line_id: String,
+    pub nearest_point: Point,
+    pub nearest_position: GeoPosition,
+    // where the distance is measured from, the closest point on the lot or the location itself
+    pub query_point: Point,
+    pub query_position: GeoPosition,
+    // the span of the line where the nearest point is, and its index in the line
+    pub nearest_segment: LineSegment,
+    pub segment_index: usize,
+    // length along the line from its first point to the nearest point
+    pub distance_along_m: f64,
+    // the clearance band of the distance, by the policy of the query
+    pub band: Band,
+    // the line goes over the lot, always false when the query is a point
+    pub crosses_lot: bool,
+}
+
+#[derive(Debug)]
+pub struct DistanceReport {
+    // only available when the query is made by address
+    pub full_address: Option<String>,
+    pub position: GeoPosition,
+    // every point in the report is projected with this projection
+    pub projection: Projection,
+    pub location: Point,
+    // the lot queried, `location` is its centroid
+    pub lot: Option<Polygon>,
+    // sorted by voltage, highest first
+    pub distances: Vec<VoltageDistance>,
+    // the suburb the place lies in, only known when its boundary is
+    pub suburb: Option<String>,
+    pub suburbs_searched: Vec<String>,
+    // distance to the furthest suburb searched
+    pub searched_radius_m: f64,
+}
+
+#[derive(Debug)]
+pub struct NearbyLine {
+    pub id: String,
+    pub voltage: u16,
+    // the suburb the line is fetched from
+    pub suburb: String,
+    pub distance_m: f64,
+    // length of the line within the radius, on the projected plane
+    pub length_within_m: f64,
+    pub positions: Vec<GeoPosition>,
+}
+
+#[derive(Debug)]
+pub struct NearbyReport {
+    // only available when the query is made by address
+    pub full_address: Option<String>,
+    pub position: GeoPosition,
+    pub radius_m: f64,
+    // sorted by distance, nearest first
+    pub lines: Vec<NearbyLine>,
+    pub suburbs_searched: Vec<String>,
+}
+
+#[cfg(test)]
+impl VoltageDistance {
+    // the line is a horizontal span `distance_m` away from the origin.
+    pub fn new_for_test(voltage: u16, distance_m: f64, line_id: &str) -> Self {
+        let nearest_point = Point {
+            x: 0.0,
+            y: distance_m,
+        };
+        VoltageDistance {
+            voltage,
+            distance_m,
+            line_id: line_id.to_owned(),
+            nearest_position: nearest_point.to_geo_position(),
+            nearest_point,
+            query_position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            query_point: Point { x: 0.0, y: 0.0 },
+            nearest_segment: LineSegment::new(
+                Point {
+                    x: -10.0,
+                    y: distance_m,
+                },
+                Point {
+                    x: 10.0,
+                    y: distance_m,
+                },
+            ),
+            segment_index: 0,
+            distance_along_m: 10.0,
+            band: ClearancePolicy::default().classify(voltage, distance_m),
+            crosses_lot: false,
+        }
+    }
+}
+
+impl DistanceReport {
+    pub fn distance_for(&self, voltage: u16) -> Option<&VoltageDistance> {
+        self.distances.iter().find(|d| d.voltage == voltage)
+    }
+
+    // a lower voltage line is only interesting when it's closer than any higher voltage one.
+    pub fn decreasing_distances(&self) -> Vec<&VoltageDistance> {
+        let mut min_distance = f64::INFINITY;
+        let mut distances = Vec::<&VoltageDistance>::new();
+        for distance in &self.distances {
+            if distance.distance_m < min_distance {
+                min_distance = distance.distance_m;
+                distances.push(distance);
+            }
+        }
+        distances
+    }
+}
+
+impl DistanceQuery {
+    pub fn new(location: QueryLocation) -> Self {
+        DistanceQuery {
+            location,
+            search_radius_m: DEFAULT_SEARCH_RADIUS_M,
+            max_search_radius_m: DEFAULT_MAX_SEARCH_RADIUS_M,
+            cache_policy: CachePolicy::default(),
+            geocoder: GeocoderConfig::default(),
+            disambiguation: Disambiguation::default(),
+            projection_origin: ProjectionOrigin::default(),
+            projection_method: ProjectionMethod::Sinusoidal,
+            distance_model: DistanceModel::Planar,
+            policy: ClearancePolicy::default(),
+        }
+    }
+
+    pub fn from_address(address: &str) -> Self {
+        DistanceQuery::new(QueryLocation::Address(address.to_owned()))
+    }
+
+    pub fn from_coordinates(latitude_degree: f64, longitude_degree: f64) -> Self {
+        DistanceQuery::new(QueryLocation::Coordinates {
+            latitude_degree,
+            longitude_degree,
+        })
+    }
+
+    // distances are from any part of the lot instead of a single point
+    pub fn from_lot(ring: Vec<GeoPosition>) -> Self {
+        DistanceQuery::new(QueryLocation::Lot(ring))
+    }
+
+    pub fn search_radius(mut self, range_m: f64) -> Self {
+        self.search_radius_m = range_m;
+        self
+    }
+
+    pub fn max_search_radius(mut self, range_m: f64) -> Self {
+        self.max_search_radius_m = range_m;
+        self
+    }
+
+    pub fn cache_policy(mut self, cache_policy: CachePolicy) -> Self {
+        self.cache_policy = cache_policy;
+        self
+    }
+
+    pub fn geocoder(mut self, geocoder: GeocoderConfig) -> Self {
+        self.geocoder = geocoder;
+        self
+    }
+
+    pub fn disambiguation(mut self, disambiguation: Disambiguation) -> Self {
+        self.disambiguation = disambiguation;
+        self
+    }
+
+    pub fn projection_origin(mut self, projection_origin: ProjectionOrigin) -> Self {
+        self.projection_origin = projection_origin;
+        self
+    }
+
+    pub fn projection_method(mut self, projection_method: ProjectionMethod) -> Self {
+        self.projection_method = projection_method;
+        self
+    }
+
+    pub fn distance_model(mut self, distance_model: DistanceModel) -> Self {
+        self.distance_model = distance_model;
+        self
+    }
+
+    pub fn policy(mut self, policy: ClearancePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    // A suburb is skipped once `distance_to_beat` is no further than where the lines of the
+    // suburb are expected, and suburbs further than the max search radius are never searched.
+    fn should_search(&self, candidate: &SuburbCandidate, distance_to_beat: f64) -> bool {
+        if candidate.lower_bound > self.max_search_radius_m {
+            return false;
+        }
+        candidate.distance < self.search_radius_m || distance_to_beat > candidate.lower_bound
+    }
+
+    fn projection_for(&self, position: &GeoPosition) -> Projection {
+        let origin = match &self.projection_origin {
+            ProjectionOrigin::Query => position.clone(),
+            ProjectionOrigin::Fixed(origin) => origin.clone(),
+        };
+        Projection::new(origin, self.projection_method.clone())
+    }
+
+    pub fn run(&self) -> Result<DistanceReport> {
+        QuerySession::new(&self.cache_policy)?
+            .with_geocoder(self.geocoder.build()?)
+            .with_disambiguation(self.disambiguation.clone())
+            .run(self)
+    }
+
+    pub fn run_detailed(&self) -> Result<(DistanceReport, SearchDetails)> {
+        QuerySession::new(&self.cache_policy)?
+            .with_geocoder(self.geocoder.build()?)
+            .with_disambiguation(self.disambiguation.clone())
+            .run_detailed(self)
+    }
+
+    // every line within `radius_m`, instead of the nearest one per voltage
+    pub fn run_nearby(&self, radius_m: f64) -> Result<NearbyReport> {
+        QuerySession::new(&self.cache_policy)?
+            .with_geocoder(self.geocoder.build()?)
+            .with_disambiguation(self.disambiguation.clone())
+            .nearby(self, radius_m)
+    }
+}
+
+// a suburb searched for a report, projected with the projection of the report
+pub struct SearchedSuburb {
+    pub info: SuburbInfo,
+    pub boundary: Option<Polygon>,
+}
+
+// everything searched for a report, e.g. to be exported
+pub struct SearchDetails {
+    pub suburbs: Vec<SearchedSuburb>,
+    pub high_voltage_lines: HashMap<u16, Vec<HighVoltageLine>>,
+}
+
+// A suburb fetched, parsed on the plane of a projection.
+struct ParsedSuburb {
+    high_voltage_lines: HashMap<u16, Vec<HighVoltageLine>>,
+    // every span of `high_voltage_lines`, for each voltage
+    line_index: HashMap<u16, LineIndex>,
+    boundary: Option<Polygon>,
+    extent: Option<BoundingBox>,
+}
+
+impl ParsedSuburb {
+    fn new(suburb_id: u16, raw: &SelectSuburbResponse, projection: &Projection) -> Self {
+        let high_voltage_lines = data_source::parse_high_voltage_lines(suburb_id, raw, projection);
+        ParsedSuburb {
+            line_index: data_source::index_high_voltage_lines(&high_voltage_lines),
+            high_voltage_lines,
+            boundary: parse_boundary(suburb_id, raw, projection),
+            extent: fetched_extent(raw, projection),
+        }
+    }
+}
+
+// Keeps the suburb table and every suburb fetched so far, so that running many queries
+// (e.g. in batch mode) only fetches each suburb once. Each suburb is also parsed and indexed
+// once for as long as the queries are projected the same way, i.e. with a fixed projection
+// origin. With the origin at each query, suburbs are parsed again for every query.
+pub struct QuerySession {
+    cache: api::cache::Cache,
+    geocoder: Box<dyn Geocoder>,
+    disambiguation: Disambiguation,
+    suburbs_info: Vec<SuburbInfo>,
+    // suburb id -> response from propertydatamap
+    suburb_responses: HashMap<u16, SelectSuburbResponse>,
+    // suburb id -> the suburb parsed on the plane of `parsed_projection`
+    parsed_suburbs: HashMap<u16, ParsedSuburb>,
+    parsed_projection: Option<Projection>,
+    // every voltage of the lines parsed so far
+    voltages_seen: BTreeSet<u16>,
+}
+
+impl QuerySession {
+    pub fn new(cache_policy: &CachePolicy) -> Result<Self> {
+        let cache = match cache_policy {
+            CachePolicy::Enabled(file_path) => api::cache::Cache::new(file_path.clone(), true)?,
+            CachePolicy::Disabled => api::cache::Cache::new(path::PathBuf::new(), false)?,
+        };
+        let raw_suburb_map = api::property_data_map::server_init_init(&cache)?;
+        let suburbs_info: Vec<SuburbInfo> =
+            data_source::get_all_suburbs(&raw_suburb_map, &Projection::default());
+        debug!("postcode_to_suburb_id calculated");
+        Ok(QuerySession {
+            cache,
+            geocoder: GeocoderConfig::default().build()?,
+            disambiguation: Disambiguation::default(),
+            suburbs_info,
+            suburb_responses: HashMap::new(),
+            parsed_suburbs: HashMap::new(),
+            parsed_projection: None,
+            voltages_seen: BTreeSet::new(),
+        })
+    }
+
+    pub fn with_geocoder(mut self, geocoder: Box<dyn Geocoder>) -> Self {
+        self.geocoder = geocoder;
+        self
+    }
+
+    pub fn with_disambiguation(mut self, disambiguation: Disambiguation) -> Self {
+        self.disambiguation = disambiguation;
+        self
+    }
+
+    // the cache policy, the geocoder and the disambiguation of the query are ignored, the ones of
+    // the session are used instead.
+    pub fn run(&mut self, query: &DistanceQuery) -> Result<DistanceReport> {
+        self.search(query).map(|(report, _)| report)
+    }
+
+    pub fn run_detailed(
+        &mut self,
+        query: &DistanceQuery,
+    ) -> Result<(DistanceReport, SearchDetails)> {
+        let (report, searched) = self.search(query)?;
+        let mut cached_line_id = HashSet::<String>::new();
+        let mut high_voltage_lines = HashMap::<u16, Vec<HighVoltageLine>>::new();
+        let mut suburbs = Vec::<SearchedSuburb>::new();
+        for id in searched {
+            let parsed = &self.parsed_suburbs[&id];
+            high_voltage_lines = aggregate_high_voltage_lines(
+                high_voltage_lines,
+                parsed.high_voltage_lines.clone(),
+                &mut cached_line_id,
+            );
+            let Some(info) = self.suburbs_info.iter().find(|s| s.id == id) else {
+                continue;
+            };
+            suburbs.push(SearchedSuburb {
+                info: SuburbInfo {
+                    name: info.name.to_owned(),
+                    id: info.id,
+                    postcode: info.postcode,
+                    position: info.position.clone(),
+                    location: report.projection.project(&info.position),
+                },
+                boundary: parsed.boundary.clone(),
+            });
+        }
+        let details = SearchDetails {
+            suburbs,
+            high_voltage_lines,
+        };
+        Ok((report, details))
+    }
+
+    // the report, and the id of every suburb searched for it
+    fn search(&mut self, query: &DistanceQuery) -> Result<(DistanceReport, Vec<u16>)> {
+        let Located {
+            full_address,
+            position,
+            projection,
+            location,
+            lot,
+        } = self.locate(query)?;
+        let slack = lot.as_ref().map_or(0.0, |lot| lot_slack(lot, &location));
+        let candidates = self.suburb_candidates(&location, &projection);
+        let mut suburb = None;
+        let mut searched = Vec::<u16>::new();
+        let mut suburbs_searched = Vec::<String>::new();
+        let mut searched_radius_m = 0.0_f64;
+        // planar distance to the nearest line of each voltage found so far
+        let mut best_distances = HashMap::<u16, f64>::new();
+        for candidate in candidates.iter() {
+            let to_beat = distance_to_beat(&best_distances, &self.voltages_seen);
+            if !query.should_search(candidate, to_beat) {
+                continue;
+            }
+            self.load_suburb(candidate.id, &candidate.name)?;
+            let parsed = self.parse_suburb(candidate.id, &projection);
+            if parsed
+                .boundary
+                .as_ref()
+                .is_some_and(|b| b.contains(&location))
+            {
+                suburb = Some(candidate.name.to_owned());
+            }
+            for (voltage, index) in parsed.line_index.iter() {
+                let Some((_, _, distance)) = index.nearest(&location) else {
+                    continue;
+                };
+                let best = best_distances.entry(*voltage).or_insert(f64::INFINITY);
+                *best = best.min(distance + slack);
+            }
+            searched.push(candidate.id);
+            suburbs_searched.push(candidate.name.to_owned());
+            searched_radius_m = searched_radius_m.max(candidate.distance);
+        }
+        debug!(
+            "suburbs within {:.0}m searched: {:?}",
+            searched_radius_m, suburbs_searched
+        );
+
+        let searched_suburbs = searched
+            .iter()
+            .map(|id| &self.parsed_suburbs[id])
+            .collect::<Vec<&ParsedSuburb>>();
+        let distances = match &lot {
+            Some(lot) => find_lot_distances(
+                lot,
+                &projection,
+                &query.distance_model,
+                &query.policy,
+                &searched_suburbs,
+            ),
+            None => find_distances(
+                &location,
+                &position,
+                &projection,
+                &query.distance_model,
+                &query.policy,
+                &searched_suburbs,
+            ),
+        };
+        debug!("distances found {:?}", distances);
+        let report = DistanceReport {
+            full_address,
+            position,
+            projection,
+            location,
+            lot,
+            distances,
+            suburb,
+            suburbs_searched,
+            searched_radius_m,
+        };
+        Ok((report, searched))
+    }
+
+    // Every suburb that can have a line within the radius is searched, no matter what's found.
+    pub fn nearby(&mut self, query: &DistanceQuery, radius_m: f64) -> Result<NearbyReport> {
+        let Located {
+            full_address,
+            position,
+            projection,
+            location,
+            ..
+        } = self.locate(query)?;
+        let candidates = self.suburb_candidates(&location, &projection);
+        let mut searched = Vec::<u16>::new();
+        let mut suburbs_searched = Vec::<String>::new();
+        for candidate in candidates.iter() {
+            if candidate.lower_bound > radius_m {
+                break;
+            }
+            self.load_suburb(candidate.id, &candidate.name)?;
+            self.parse_suburb(candidate.id, &projection);
+            searched.push(candidate.id);
+            suburbs_searched.push(candidate.name.to_owned());
+        }
+        debug!(
+            "suburbs within {:.0}m searched: {:?}",
+            radius_m, suburbs_searched
+        );
+        let lines = find_nearby_lines(
+            &location,
+            &position,
+            &query.distance_model,
+            searched
+                .iter()
+                .flat_map(|id| self.parsed_suburbs[id].high_voltage_lines.iter()),
+            radius_m,
+            &self.suburbs_info,
+        );
+        Ok(NearbyReport {
+            full_address,
+            position,
+            radius_m,
+            lines,
+            suburbs_searched,
+        })
+    }
+
+    fn locate(&self, query: &DistanceQuery) -> Result<Located> {
+        match &query.location {
+            QueryLocation::Address(address) => {
+                let address = disambiguation::resolve(
+                    address,
+                    self.geocoder.search(address)?,
+                    &self.suburbs_info,
+                    &self.disambiguation,
+                )?;
+                let position =
+                    GeoPosition::from_degree(address.latitude_degree, address.longitude_degree);
+                let projection = query.projection_for(&position);
+                Ok(Located {
+                    full_address: Some(address.full_address.to_owned()),
+                    location: data_source::parse_address(address, &projection),
+                    position,
+                    projection,
+                    lot: None,
+                })
+            }
+            QueryLocation::Coordinates {
+                latitude_degree,
+                longitude_degree,
+            } => {
+                let position = GeoPosition::from_degree(*latitude_degree, *longitude_degree);
+                let projection = query.projection_for(&position);
+                let location = data_source::parse_coordinates(
+                    *latitude_degree,
+                    *longitude_degree,
+                    &projection,
+                )?;
+                Ok(Located {
+                    full_address: None,
+                    position,
+                    projection,
+                    location,
+                    lot: None,
+                })
+            }
+            // projected around the mean of its vertices, a lot is too small for it to matter
+            QueryLocation::Lot(ring) => {
+                let count = ring.len() as f64;
+                let mean = GeoPosition::from_degree(
+                    ring.iter().map(|p| p.latitude_degree()).sum::<f64>() / count,
+                    ring.iter().map(|p| p.longitude_degree()).sum::<f64>() / count,
+                );
+                let projection = query.projection_for(&mean);
+                let vertices = ring
+                    .iter()
+                    .map(|p| {
+                        data_source::parse_coordinates(
+                            p.latitude_degree(),
+                            p.longitude_degree(),
+                            &projection,
+                        )
+                    })
+                    .collect::<Result<Vec<Point>>>()?;
+                let lot =
+                    Polygon::new(vertices).map_err(|e| HvError::MalformedLot(e.to_string()))?;
+                let location = lot.centroid();
+                Ok(Located {
+                    full_address: None,
+                    position: projection.unproject(&location),
+                    projection,
+                    location,
+                    lot: Some(lot),
+                })
+            }
+        }
+    }
+
+    // every suburb fetched so far is parsed on the plane of the query to be ranked
+    fn suburb_candidates(
+        &mut self,
+        place: &Point,
+        projection: &Projection,
+    ) -> Vec<SuburbCandidate> {
+        let fetched = self.suburb_responses.keys().copied().collect::<Vec<u16>>();
+        for suburb_id in fetched {
+            self.parse_suburb(suburb_id, projection);
+        }
+        rank_suburbs(&self.suburbs_info, &self.parsed_suburbs, place, projection)
+    }
+
+    // The suburb must be fetched. Suburbs parsed on another plane are dropped, since nothing of
+    // them can be reused.
+    fn parse_suburb(&mut self, suburb_id: u16, projection: &Projection) -> &ParsedSuburb {
+        if self.parsed_projection.as_ref() != Some(projection) {
+            self.parsed_suburbs.clear();
+            self.parsed_projection = Some(projection.clone());
+        }
+        let raw = &self.suburb_responses[&suburb_id];
+        let parsed = self
+            .parsed_suburbs
+            .entry(suburb_id)
+            .or_insert_with(|| ParsedSuburb::new(suburb_id, raw, projection));
+        self.voltages_seen
+            .extend(parsed.high_voltage_lines.keys().copied());
+        parsed
+    }
+
+    fn load_suburb(&mut self, suburb_id: u16, suburb_name: &str) -> Result<&SelectSuburbResponse> {
+        if !self.suburb_responses.contains_key(&suburb_id) {
+            let raw = api::property_data_map::select_suburb(suburb_id, suburb_name, &self.cache)?;
+            self.suburb_responses.insert(suburb_id, raw);
+        }
+        Ok(&self.suburb_responses[&suburb_id])
+    }
+}
+
+// Each voltage is reported with its own nearest line, so the search goes on until no suburb left
+// is expected to beat the furthest of them, not only the nearest. A voltage seen in the session
+// but not found for this query yet can be in any suburb left.
+// A line at `d` from the centroid of the lot is at most `d + gap` from the lot, where `gap` is
+// how far the centroid is outside the lot, 0 unless the lot is concave. A line in an unsearched
+// suburb is at least `reach` closer to the lot than to the centroid, with `reach` the farthest
+// vertex from the centroid. The lot distance can only be beaten by a line within `d + slack` of
+// the centroid.
+fn lot_slack(lot: &Polygon, location: &Point) -> f64 {
+    lot.distance_to(location) + lot_reach(lot, location)
+}
+
+// no point of the lot is farther from `location` than this
+fn lot_reach(lot: &Polygon, location: &Point) -> f64 {
+    lot.get_vertices()
+        .iter()
+        .map(|v| v.distance_to(location))
+        .fold(0.0, f64::max)
+}
+
+fn distance_to_beat(best_distances: &HashMap<u16, f64>, voltages_seen: &BTreeSet<u16>) -> f64 {
+    if voltages_seen
+        .iter()
+        .any(|voltage| !best_distances.contains_key(voltage))
+    {
+        return f64::INFINITY;
+    }
+    best_distances
+        .values()
+        .copied()
+        .reduce(f64::max)
+        .unwrap_or(f64::INFINITY)
+}
+
+// the address given, where it is, and where it is on the plane of the query
+struct Located {
+    full_address: Option<String>,
+    position: GeoPosition,
+    projection: Projection,
+    location: Point,
+    lot: Option<Polygon>,
+}
+
+struct SuburbCandidate {
+    id: u16,
+    name: String,
+    // to the boundary when it's known, otherwise to the centroid
+    distance: f64,
+    // no line of the suburb can be closer than this once it's fetched, before that it's only
+    // an estimate
+    lower_bound: f64,
+}
+
+impl SuburbCandidate {
+    // `extent` is the box around everything fetched for the suburb, its lines and its boundary.
+    // A suburb not fetched yet is assumed to have its lines within `ASSUMED_SUBURB_RADIUS_M` from
+    // where it is.
+    fn new(
+        suburb: &SuburbInfo,
+        place: &Point,
+        projection: &Projection,
+        boundary: Option<&Polygon>,
+        extent: Option<&BoundingBox>,
+    ) -> Self {
+        let distance = match boundary {
+            Some(boundary) => boundary.distance_to(place),
+            None => projection.project(&suburb.position).distance_to(place),
+        };
+        let lower_bound = match extent {
+            Some(extent) => extent.distance_to(place),
+            None => (distance - ASSUMED_SUBURB_RADIUS_M).max(0.0),
+        };
+        SuburbCandidate {
+            id: suburb.id,
+            name: suburb.name.to_owned(),
+            distance,
+            lower_bound,
+        }
+    }
+}
+
+// Boundaries only come with the lines of a suburb, so only suburbs fetched before in the session,
+// i.e. the ones in `parsed_suburbs`, are ranked by their boundary. The others are ranked by their centroid, fetching every boundary
+// up front would be a request per suburb of the state. A suburb is still searched once its
+// centroid is close enough, and its boundary is then used to locate the query.
+fn rank_suburbs(
+    suburbs_info: &[SuburbInfo],
+    parsed_suburbs: &HashMap<u16, ParsedSuburb>,
+    place: &Point,
+    projection: &Projection,
+) -> Vec<SuburbCandidate> {
+    let mut candidates = suburbs_info
+        .iter()
+        .map(|s| {
+            let parsed = parsed_suburbs.get(&s.id);
+            SuburbCandidate::new(
+                s,
+                place,
+                projection,
+                parsed.and_then(|p| p.boundary.as_ref()),
+                parsed.and_then(|p| p.extent.as_ref()),
+            )
+        })
+        .collect::<Vec<SuburbCandidate>>();
+    candidates.sort_by(|a, b| a.lower_bound.total_cmp(&b.lower_bound));
+    candidates
+}
+
+// the box around every vertex of the lines and of the boundary, the lines are straight between
+// their vertices on the plane, so they are all inside
+fn fetched_extent(raw: &SelectSuburbResponse, projection: &Projection) -> Option<BoundingBox> {
+    let line_vertices = raw
+        .selected_lat_lon
+        .values()
+        .flat_map(|line| line.coordinates.iter().map(|p| (p[1], p[0])));
+    let boundary_vertices = raw
+        .boundary
+        .iter()
+        .flat_map(|b| b.coordinates.iter().flatten().map(|p| (p[1], p[0])));
+    let points = line_vertices
+        .chain(boundary_vertices)
+        .map(|(latitude, longitude)| {
+            projection.project(&GeoPosition::from_degree(latitude, longitude))
+        })
+        .collect::<Vec<Point>>();
+    BoundingBox::from_points(&points)
+}
+
+// a boundary that can not be parsed is the same as not having one
+fn parse_boundary(
+    suburb_id: u16,
+    raw: &SelectSuburbResponse,
+    projection: &Projection,
+) -> Option<Polygon> {
+    let boundary = raw.boundary.as_ref()?;
+    match data_source::parse_suburb_boundary(suburb_id, boundary, projection) {
+        Ok(boundary) => Some(boundary),
+        Err(e) => {
+            debug!("boundary of suburb {} ignored: {}", suburb_id, e);
+            None
+        }
+    }
+}
+
+fn aggregate_high_voltage_lines(
+    mut acc: HashMap<u16, Vec<HighVoltageLine>>,
+    map: HashMap<u16, Vec<HighVoltageLine>>,
+    cached_line_id: &mut HashSet<String>,
+) -> HashMap<u16, Vec<HighVoltageLine>> {
+    for (k, v) in map {
+        let mut lines = Vec::<HighVoltageLine>::new();
+        for line in v {
+            if cached_line_id.contains(&line.id) {
+                continue;
+            }
+            cached_line_id.insert(line.id.to_owned());
+            lines.push(line);
+        }
+        match acc.get_mut(&k) {
+            Some(existing_lines) => {
+                existing_lines.extend(lines);
+            }
+            None => {
+                acc.insert(k, lines);
+            }
+        }
+    }
+    acc
+}
+
+// a line in many suburbs is only listed once
+fn find_nearby_lines<'a>(
+    location: &Point,
+    position: &GeoPosition,
+    distance_model: &DistanceModel,
+    high_voltage_lines: impl Iterator<Item = (&'a u16, &'a Vec<HighVoltageLine>)>,
+    radius_m: f64,
+    suburbs_info: &[SuburbInfo],
+) -> Vec<NearbyLine> {
+    let mut listed = HashSet::<&str>::new();
+    let mut nearby = high_voltage_lines
+        .flat_map(|(voltage, lines)| lines.iter().map(move |l| (*voltage, l)))
+        .filter(|(_, line)| listed.insert(&line.id))
+        .filter_map(|(voltage, line)| {
+            let distance_m = line.distance_to(location, position, distance_model);
+            if distance_m > radius_m {
+                return None;
+            }
+            let suburb = suburbs_info
+                .iter()
+                .find(|s| s.id == line.suburb_id)
+                .map_or_else(|| line.suburb_id.to_string(), |s| s.name.to_owned());
+            Some(NearbyLine {
+                length_within_m: line.line.length_within(location, radius_m),
+                id: line.id.to_owned(),
+                voltage,
+                suburb,
+                distance_m,
+                positions: line.positions.clone(),
+            })
+        })
+        .collect::<Vec<NearbyLine>>();
+    nearby.sort_by(|a, b| {
+        a.distance_m
+            .total_cmp(&b.distance_m)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    nearby
+}
+
+fn voltages_of(suburbs: &[&ParsedSuburb]) -> BTreeSet<u16> {
+    suburbs
+        .iter()
+        .flat_map(|s| s.high_voltage_lines.keys().copied())
+        .collect()
+}
+
+// the lines of the voltage in each suburb, with their index
+fn indexed_lines<'a>(
+    suburbs: &[&'a ParsedSuburb],
+    voltage: u16,
+) -> Vec<(&'a Vec<HighVoltageLine>, &'a LineIndex)> {
+    suburbs
+        .iter()
+        .filter_map(|s| {
+            Some((
+                s.high_voltage_lines.get(&voltage)?,
+                s.line_index.get(&voltage)?,
+            ))
+        })
+        .collect()
+}
+
+// The closest points between the lot and every span of the lines are found on the projected plane,
+// only the distance between them follows the distance model.
+// The span nearest the centroid is at some distance from the lot, any span closer to the lot is
+// within that distance plus the reach of the lot from the centroid, so only those are measured.
+fn find_lot_distances(
+    lot: &Polygon,
+    projection: &Projection,
+    distance_model: &DistanceModel,
+    policy: &ClearancePolicy,
+    suburbs: &[&ParsedSuburb],
+) -> Vec<VoltageDistance> {
+    let centroid = lot.centroid();
+    let reach = lot_reach(lot, &centroid);
+    let mut distances = Vec::<VoltageDistance>::new();
+    for voltage in voltages_of(suburbs) {
+        let indexed = indexed_lines(suburbs, voltage);
+        let Some(radius) = indexed
+            .iter()
+            .filter_map(|(_, index)| index.nearest(&centroid))
+            .map(|(segment, _, _)| lot.distance_to_segment(segment) + reach)
+            .reduce(f64::min)
+        else {
+            continue;
+        };
+        let nearest = indexed
+            .iter()
+            .flat_map(|(lines, index)| {
+                index.within(&centroid, radius).into_iter().map(
+                    move |(segment, (line_idx, segment_idx), _)| {
+                        (&lines[*line_idx], *segment_idx, segment)
+                    },
+                )
+            })
+            .map(|(line, segment_idx, segment)| {
+                (line, segment_idx, lot.closest_points_to_segment(segment))
+            })
+            .min_by(|(_, _, (p0, q0)), (_, _, (p1, q1))| {
+                p0.distance_to(q0).total_cmp(&p1.distance_to(q1))
+            });
+        let Some((line, segment_index, (on_lot, on_line))) = nearest else {
+            continue;
+        };
+        let segments = line.line.segments();
+        let planar_distance = on_lot.distance_to(&on_line);
+        let nearest_position = projection.unproject(&on_line);
+        let query_position = projection.unproject(&on_lot);
+        let distance = match distance_model {
+            _ if planar_distance == 0.0 => 0.0,
+            DistanceModel::Planar => planar_distance,
+            DistanceModel::Spherical => haversine_distance(&query_position, &nearest_position),
+            DistanceModel::Ellipsoidal => vincenty_distance(&query_position, &nearest_position),
+        };
+        distances.push(VoltageDistance {
+            voltage,
+            distance_m: distance,
+            line_id: line.id.to_owned(),
+            nearest_position,
+            nearest_segment: segments[segment_index].clone(),
+            segment_index,
+            distance_along_m: segments[..segment_index]
+                .iter()
+                .map(|s| s.length())
+                .sum::<f64>()
+                + segments[segment_index].a.distance_to(&on_line),
+            nearest_point: on_line,
+            query_point: on_lot,
+            query_position,
+            band: policy.classify(voltage, distance),
+            crosses_lot: planar_distance == 0.0,
+        });
+    }
+    distances.sort_by_key(|d| std::cmp::Reverse(d.voltage));
+    distances
+}
+
+// the nearest point is always found on the projected plane, only the distance itself follows
+// the distance model.
+fn find_distances(
+    location: &Point,
+    position: &GeoPosition,
+    projection: &Projection,
+    distance_model: &DistanceModel,
+    policy: &ClearancePolicy,
+    suburbs: &[&ParsedSuburb],
+) -> Vec<VoltageDistance> {
+    let mut distances = Vec::<VoltageDistance>::new();
+    for voltage in voltages_of(suburbs) {
+        let indexed = indexed_lines(suburbs, voltage);
+        let planar_nearest = indexed
+            .iter()
+            .filter_map(|(lines, index)| {
+                let (_, (line_idx, _), distance) = index.nearest(location)?;
+                Some((&lines[*line_idx], distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let nearest = match (planar_nearest, distance_model) {
+            (None, _) => None,
+            (nearest, DistanceModel::Planar) => nearest,
+            // the nearest line on the earth can be a different one when two lines are almost
+            // equally far away, every line close enough on the plane is measured again.
+            (Some((_, planar_distance)), _) => {
+                let candidates = indexed
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(suburb_idx, (_, index))| {
+                        index
+                            .within(location, planar_distance * 1.01 + 1.0)
+                            .into_iter()
+                            .map(move |(_, (line_idx, _), _)| (suburb_idx, *line_idx))
+                    })
+                    .collect::<BTreeSet<(usize, usize)>>();
+                candidates
+                    .into_iter()
+                    .map(|(suburb_idx, line_idx)| {
+                        let line = &indexed[suburb_idx].0[line_idx];
+                        (line, line.distance_to(location, position, distance_model))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+            }
+        };
+        if let Some((line, distance)) = nearest {
+            let closest = line.closest_point_to(location);
+            distances.push(VoltageDistance {
+                voltage,
+                distance_m: distance,
+                line_id: line.id.to_owned(),
+                nearest_position: projection.unproject(&closest.point),
+                nearest_point: closest.point,
+                query_point: location.clone(),
+                query_position: position.clone(),
+                nearest_segment: line.line.segments()[closest.segment_index].clone(),
+                segment_index: closest.segment_index,
+                distance_along_m: closest.distance_along,
+                band: policy.classify(voltage, distance),
+                crosses_lot: false,
+            });
+        }
+    }
+    distances.sort_by_key(|d| std::cmp::Reverse(d.voltage));
+    distances
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::property_data_map::SuburbPolygon;
+    use crate::geometry::PolyLine;
+
+    fn line(id: &str, points: Vec<Point>) -> HighVoltageLine {
+        HighVoltageLine {
+            positions: points
+                .iter()
+                .map(|p| Projection::default().unproject(p))
+                .collect(),
+            line: PolyLine::new(points).unwrap(),
+            id: id.to_owned(),
+            suburb_id: 1,
+        }
+    }
+
+    #[test]
+    fn aggregate_skips_duplicated_lines() {
+        let mut cached_line_id = HashSet::<String>::new();
+        let first = HashMap::from([(
+            132,
+            vec![line(
+                "1",
+                vec![Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }],
+            )],
+        )]);
+        let second = HashMap::from([(
+            132,
+            vec![
+                line(
+                    "1",
+                    vec![Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }],
+                ),
+                line(
+                    "2",
+                    vec![Point { x: 0.0, y: 1.0 }, Point { x: 1.0, y: 1.0 }],
+                ),
+            ],
+        )]);
+        let acc = aggregate_high_voltage_lines(HashMap::new(), first, &mut cached_line_id);
+        let acc = aggregate_high_voltage_lines(acc, second, &mut cached_line_id);
+        let ids = acc[&132]
+            .iter()
+            .map(|l| l.id.to_owned())
+            .collect::<Vec<String>>();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+
+    fn lines_for_find_distances() -> HashMap<u16, Vec<HighVoltageLine>> {
+        HashMap::from([
+            (
+                132,
+                vec![
+                    line(
+                        "far",
+                        vec![Point { x: 0.0, y: 100.0 }, Point { x: 10.0, y: 100.0 }],
+                    ),
+                    line(
+                        "near",
+                        vec![Point { x: 0.0, y: 10.0 }, Point { x: 10.0, y: 10.0 }],
+                    ),
+                ],
+            ),
+            (
+                330,
+                vec![line(
+                    "330",
+                    vec![Point { x: 20.0, y: 0.0 }, Point { x: 20.0, y: 10.0 }],
+                )],
+            ),
+        ])
+    }
+
+    fn parsed(high_voltage_lines: HashMap<u16, Vec<HighVoltageLine>>) -> ParsedSuburb {
+        ParsedSuburb {
+            line_index: data_source::index_high_voltage_lines(&high_voltage_lines),
+            high_voltage_lines,
+            boundary: None,
+            extent: None,
+        }
+    }
+
+    fn find_distances_with(distance_model: &DistanceModel) -> Vec<VoltageDistance> {
+        let location = Point { x: 5.0, y: 0.0 };
+        let projection = Projection::default();
+        find_distances(
+            &location,
+            &projection.unproject(&location),
+            &projection,
+            distance_model,
+            &ClearancePolicy::default(),
+            &[&parsed(lines_for_find_distances())],
+        )
+    }
+
+    #[test]
+    fn find_distances_per_voltage() {
+        let distances = find_distances_with(&DistanceModel::Planar);
+        assert_eq!(distances.len(), 2);
+        assert_eq!(distances[0].voltage, 330);
+        assert_eq!(distances[0].line_id, "330");
+        assert!((distances[0].distance_m - 15.0).abs() < 1e-10);
+        distances[0]
+            .nearest_point
+            .assert_close_to(&Point { x: 20.0, y: 0.0 }, 1e-10);
+        assert_eq!(distances[1].voltage, 132);
+        assert_eq!(distances[1].line_id, "near");
+        assert!((distances[1].distance_m - 10.0).abs() < 1e-10);
+        distances[1]
+            .nearest_point
+            .assert_close_to(&Point { x: 5.0, y: 10.0 }, 1e-10);
+        assert_eq!(distances[1].segment_index, 0);
+        assert!((distances[1].distance_along_m - 5.0).abs() < 1e-10);
+    }
+
+    fn lot(max_y: f64) -> Polygon {
+        Polygon::new(vec![
+            Point { x: 0.0, y: -5.0 },
+            Point { x: 8.0, y: -5.0 },
+            Point { x: 8.0, y: max_y },
+            Point { x: 0.0, y: max_y },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn find_lot_distances_from_boundary() {
+        let distances = find_lot_distances(
+            &lot(3.0),
+            &Projection::default(),
+            &DistanceModel::Planar,
+            &ClearancePolicy::default(),
+            &[&parsed(lines_for_find_distances())],
+        );
+        assert_eq!(distances.len(), 2);
+        assert!((distances[0].distance_m - 12.0).abs() < 1e-10);
+        assert_eq!(distances[1].line_id, "near");
+        assert!((distances[1].distance_m - 7.0).abs() < 1e-10);
+        assert!(!distances[1].crosses_lot);
+        // the nearest point on the line is over the top edge of the lot
+        assert!((distances[1].nearest_point.y - 10.0).abs() < 1e-10);
+        assert!(distances[1].nearest_point.x <= 8.0);
+        assert!((distances[1].distance_along_m - distances[1].nearest_point.x).abs() < 1e-10);
+        // measured from the top edge of the lot, right below the nearest point
+        distances[1].query_point.assert_close_to(
+            &Point {
+                x: distances[1].nearest_point.x,
+                y: 3.0,
+            },
+            1e-10,
+        );
+    }
+
+    #[test]
+    fn find_lot_distances_crossing() {
+        let distances = find_lot_distances(
+            &lot(12.0),
+            &Projection::default(),
+            &DistanceModel::Spherical,
+            &ClearancePolicy::default(),
+            &[&parsed(lines_for_find_distances())],
+        );
+        assert_eq!(distances[1].line_id, "near");
+        assert_eq!(distances[1].distance_m, 0.0);
+        assert!(distances[1].crosses_lot);
+        assert!(!distances[0].crosses_lot);
+        // ~0.3% at most between the sphere and the plane
+        assert!((distances[0].distance_m - 12.0).abs() < 0.05);
+    }
+
+    // a U open to the top, its centroid is in the notch, outside of the lot
+    fn concave_lot() -> Polygon {
+        Polygon::new(vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 30.0, y: 0.0 },
+            Point { x: 30.0, y: 30.0 },
+            Point { x: 20.0, y: 30.0 },
+            Point { x: 20.0, y: 10.0 },
+            Point { x: 10.0, y: 10.0 },
+            Point { x: 10.0, y: 30.0 },
+            Point { x: 0.0, y: 30.0 },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn find_lot_distances_concave() {
+        let lot = concave_lot();
+        assert!(!lot.contains(&lot.centroid()));
+        // "notch" is the nearest to the centroid, but "side" is the nearest to the lot
+        let high_voltage_lines = HashMap::from([(
+            132,
+            vec![
+                line(
+                    "notch",
+                    vec![Point { x: 14.0, y: 25.0 }, Point { x: 16.0, y: 25.0 }],
+                ),
+                line(
+                    "side",
+                    vec![
+                        Point { x: -40.0, y: 60.0 },
+                        Point { x: -1.0, y: 30.0 },
+                        Point { x: -1.0, y: 0.0 },
+                    ],
+                ),
+            ],
+        )]);
+        let distances = find_lot_distances(
+            &lot,
+            &Projection::default(),
+            &DistanceModel::Planar,
+            &ClearancePolicy::default(),
+            &[&parsed(high_voltage_lines)],
+        );
+        assert_eq!(distances.len(), 1);
+        assert_eq!(distances[0].line_id, "side");
+        assert!((distances[0].distance_m - 1.0).abs() < 1e-10);
+        assert_eq!(distances[0].segment_index, 1);
+    }
+
+    #[test]
+    fn lot_slack_covers_centroid_outside() {
+        let concave = concave_lot();
+        let centroid = concave.centroid();
+        let reach = lot_reach(&concave, &centroid);
+        // the bottom of the notch is the nearest edge to the centroid
+        let gap = centroid.y - 10.0;
+        assert!(gap > 0.0);
+        assert!((concave.distance_to(&centroid) - gap).abs() < 1e-10);
+        assert!((lot_slack(&concave, &centroid) - (reach + gap)).abs() < 1e-10);
+        // a convex lot holds its centroid
+        let convex = lot(3.0);
+        assert_eq!(
+            lot_slack(&convex, &convex.centroid()),
+            lot_reach(&convex, &convex.centroid())
+        );
+    }
+
+    #[test]
+    fn find_distances_geodesic_close_to_planar() {
+        for model in [DistanceModel::Spherical, DistanceModel::Ellipsoidal] {
+            let distances = find_distances_with(&model);
+            assert_eq!(distances.len(), 2);
+            assert_eq!(distances[1].line_id, "near");
+            // the ellipsoid is ~0.3% off the sphere the projection is based on
+            assert!((distances[0].distance_m - 15.0).abs() < 0.1);
+            assert!((distances[1].distance_m - 10.0).abs() < 0.1);
+        }
+    }
+
+    fn suburb(id: u16, x: f64, y: f64) -> SuburbInfo {
+        let location = Point { x, y };
+        SuburbInfo {
+            name: id.to_string(),
+            id,
+            postcode: 2000,
+            position: Projection::default().unproject(&location),
+            location,
+        }
+    }
+
+    fn candidate(distance: f64, boundary: Option<&Polygon>) -> SuburbCandidate {
+        SuburbCandidate::new(
+            &suburb(1, distance, 0.0),
+            &Point { x: 0.0, y: 0.0 },
+            &Projection::default(),
+            boundary,
+            boundary.map(|b| b.bounding_box()).as_ref(),
+        )
+    }
+
+    #[test]
+    fn search_expands_until_nothing_closer() {
+        let query = DistanceQuery::from_coordinates(-33.0, 151.0)
+            .search_radius(5_000.0)
+            .max_search_radius(20_000.0);
+        // always searched within the search radius
+        assert!(query.should_search(&candidate(4_000.0, None), 10.0));
+        // nothing found yet
+        assert!(query.should_search(&candidate(12_000.0, None), f64::INFINITY));
+        // a line at 9.5km can still be beaten by a suburb 12km away
+        assert!(query.should_search(&candidate(12_000.0, None), 9_500.0));
+        assert!(!query.should_search(&candidate(12_000.0, None), 8_000.0));
+        // capped
+        assert!(!query.should_search(&candidate(24_000.0, None), f64::INFINITY));
+    }
+
+    #[test]
+    fn search_until_every_voltage_is_beaten() {
+        let query = DistanceQuery::from_coordinates(-33.0, 151.0)
+            .search_radius(5_000.0)
+            .max_search_radius(20_000.0);
+        let best_distances = HashMap::from([(11, 50.0), (330, 9_500.0)]);
+        let voltages_seen = BTreeSet::from([11, 330]);
+        let to_beat = distance_to_beat(&best_distances, &voltages_seen);
+        assert_eq!(to_beat, 9_500.0);
+        // the 11kV line nearby doesn't stop the search for a closer 330kV line
+        assert!(query.should_search(&candidate(12_000.0, None), to_beat));
+        // every voltage is closer than anything the suburb can have
+        let best_distances = HashMap::from([(11, 50.0), (330, 8_000.0)]);
+        let to_beat = distance_to_beat(&best_distances, &voltages_seen);
+        assert!(!query.should_search(&candidate(12_000.0, None), to_beat));
+        // 132kV is seen in the session but not found yet
+        let voltages_seen = BTreeSet::from([11, 132, 330]);
+        let to_beat = distance_to_beat(&best_distances, &voltages_seen);
+        assert_eq!(to_beat, f64::INFINITY);
+        assert!(query.should_search(&candidate(12_000.0, None), to_beat));
+        // nothing found at all
+        assert_eq!(
+            distance_to_beat(&HashMap::new(), &BTreeSet::new()),
+            f64::INFINITY
+        );
+    }
+
+    #[test]
+    fn search_with_boundary() {
+        let query = DistanceQuery::from_coordinates(-33.0, 151.0);
+        // a long suburb with the centroid 12km away, but its boundary is only 1km away
+        let boundary = Polygon::new(vec![
+            Point {
+                x: 1_000.0,
+                y: -500.0,
+            },
+            Point {
+                x: 23_000.0,
+                y: -500.0,
+            },
+            Point {
+                x: 23_000.0,
+                y: 500.0,
+            },
+            Point {
+                x: 1_000.0,
+                y: 500.0,
+            },
+        ])
+        .unwrap();
+        let with_boundary = candidate(12_000.0, Some(&boundary));
+        assert!((with_boundary.distance - 1_000.0).abs() < 1e-9);
+        assert!(query.should_search(&with_boundary, 1_500.0));
+        assert!(!query.should_search(&candidate(12_000.0, None), 1_500.0));
+    }
+
+    #[test]
+    fn search_with_fetched_extent() {
+        let query = DistanceQuery::from_coordinates(-33.0, 151.0);
+        // a rural suburb with the centroid 12km away, and a line reaching to 1km away
+        let extent = BoundingBox {
+            min: Point {
+                x: 1_000.0,
+                y: -500.0,
+            },
+            max: Point {
+                x: 20_000.0,
+                y: 500.0,
+            },
+        };
+        let fetched = SuburbCandidate::new(
+            &suburb(1, 12_000.0, 0.0),
+            &Point { x: 0.0, y: 0.0 },
+            &Projection::default(),
+            None,
+            Some(&extent),
+        );
+        assert!((fetched.distance - 12_000.0).abs() < 1e-6);
+        assert!((fetched.lower_bound - 1_000.0).abs() < 1e-9);
+        assert!(query.should_search(&fetched, 1_500.0));
+        // only the heuristic when not fetched
+        let not_fetched = candidate(12_000.0, None);
+        assert!((not_fetched.lower_bound - 9_000.0).abs() < 1e-6);
+        assert!(!query.should_search(&not_fetched, 1_500.0));
+    }
+
+    // a response with only the boundary, a rectangle from `min` to `max` on the default plane
+    fn response_with_boundary(min: Point, max: Point) -> SelectSuburbResponse {
+        let ring = [
+            Point { x: min.x, y: min.y },
+            Point { x: max.x, y: min.y },
+            Point { x: max.x, y: max.y },
+            Point { x: min.x, y: max.y },
+            Point { x: min.x, y: min.y },
+        ]
+        .iter()
+        .map(|p| {
+            let position = Projection::default().unproject(p);
+            [position.longitude_degree(), position.latitude_degree()]
+        })
+        .collect();
+        SelectSuburbResponse {
+            selected_lat_lon: HashMap::new(),
+            selected_popup_info: HashMap::new(),
+            boundary: Some(SuburbPolygon {
+                r#type: "Polygon".to_owned(),
+                coordinates: vec![ring],
+            }),
+        }
+    }
+
+    #[test]
+    fn rank_suburbs_by_boundary_once_fetched() {
+        // a long suburb with the centroid 12km away but reaching to 1km away, and a small one 5km away
+        let suburbs_info = vec![suburb(1, 12_000.0, 0.0), suburb(2, 5_000.0, 0.0)];
+        let place = Point { x: 0.0, y: 0.0 };
+        let ids = |parsed_suburbs: &HashMap<u16, ParsedSuburb>| {
+            rank_suburbs(
+                &suburbs_info,
+                parsed_suburbs,
+                &place,
+                &Projection::default(),
+            )
+            .iter()
+            .map(|c| c.id)
+            .collect::<Vec<u16>>()
+        };
+        // not fetched, by the centroid
+        assert_eq!(ids(&HashMap::new()), vec![2, 1]);
+        let response = response_with_boundary(
+            Point {
+                x: 1_000.0,
+                y: -500.0,
+            },
+            Point {
+                x: 23_000.0,
+                y: 500.0,
+            },
+        );
+        let fetched = HashMap::from([(1, ParsedSuburb::new(1, &response, &Projection::default()))]);
+        assert_eq!(ids(&fetched), vec![1, 2]);
+        let candidates = rank_suburbs(&suburbs_info, &fetched, &place, &Projection::default());
+        assert!((candidates[0].distance - 1_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn candidate_inside_boundary() {
+        let boundary = Polygon::new(vec![
+            Point { x: -1.0, y: -1.0 },
+            Point { x: 1.0, y: -1.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: -1.0, y: 1.0 },
+        ])
+        .unwrap();
+        let inside = candidate(12_000.0, Some(&boundary));
+        assert_eq!(inside.distance, 0.0);
+        assert_eq!(inside.lower_bound, 0.0);
+    }
+
+    #[test]
+    fn nearby_lines_within_radius() {
+        let suburbs_info = vec![SuburbInfo {
+            name: "FRENCHS FOREST".to_owned(),
+            id: 1,
+            postcode: 2086,
+            position: GeoPosition::from_degree(-33.75, 151.23),
+            location: Point { x: 0.0, y: 0.0 },
+        }];
+        let high_voltage_lines = HashMap::from([
+            (
+                132,
+                vec![line(
+                    "far",
+                    vec![
+                        Point {
+                            x: -100.0,
+                            y: 2_000.0,
+                        },
+                        Point {
+                            x: 100.0,
+                            y: 2_000.0,
+                        },
+                    ],
+                )],
+            ),
+            (
+                330,
+                vec![line(
+                    "crossing",
+                    vec![
+                        Point {
+                            x: -3_000.0,
+                            y: 600.0,
+                        },
+                        Point {
+                            x: 3_000.0,
+                            y: 600.0,
+                        },
+                    ],
+                )],
+            ),
+            (
+                66,
+                vec![line(
+                    "inside",
+                    vec![Point { x: -50.0, y: 100.0 }, Point { x: 50.0, y: 100.0 }],
+                )],
+            ),
+        ]);
+        let nearby = find_nearby_lines(
+            &Point { x: 0.0, y: 0.0 },
+            &Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            &DistanceModel::Planar,
+            high_voltage_lines.iter(),
+            1_000.0,
+            &suburbs_info,
+        );
+        let ids = nearby.iter().map(|l| l.id.as_str()).collect::<Vec<&str>>();
+        assert_eq!(ids, vec!["inside", "crossing"]);
+        assert_eq!(nearby[0].voltage, 66);
+        assert_eq!(nearby[0].suburb, "FRENCHS FOREST");
+        assert!((nearby[0].distance_m - 100.0).abs() < 1e-9);
+        assert!((nearby[0].length_within_m - 100.0).abs() < 1e-9);
+        // a chord 600m from the center of a 1000m circle
+        assert!((nearby[1].length_within_m - 1_600.0).abs() < 1e-6);
+    }
+}
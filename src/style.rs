@@ -0,0 +1,86 @@
+use crate::geometry::Point;
+
+// What the kml and html outputs draw the same way.
+
+pub const RANGE_RINGS_M: [f64; 2] = [100.0, 200.0];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Colour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Colour {
+    const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Colour { red, green, blue }
+    }
+
+    // #rrggbb
+    pub fn to_html(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    // aabbggrr, always opaque
+    pub fn to_kml(self) -> String {
+        format!("ff{:02x}{:02x}{:02x}", self.blue, self.green, self.red)
+    }
+}
+
+// anything not listed is drawn in `OTHER_VOLTAGE_COLOUR`
+pub const VOLTAGE_COLOURS: [(u16, Colour); 4] = [
+    (330, Colour::rgb(0xe4, 0x1a, 0x1c)),
+    (132, Colour::rgb(0xff, 0x7f, 0x00)),
+    (66, Colour::rgb(0xe6, 0xc7, 0x00)),
+    (33, Colour::rgb(0x4d, 0xaf, 0x4a)),
+];
+pub const OTHER_VOLTAGE_COLOUR: Colour = Colour::rgb(0x88, 0x88, 0x88);
+
+pub fn voltage_colour(voltage: u16) -> Colour {
+    VOLTAGE_COLOURS
+        .iter()
+        .find(|(v, _)| *v == voltage)
+        .map_or(OTHER_VOLTAGE_COLOUR, |(_, colour)| *colour)
+}
+
+// the same for xml and html
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// points sampled evenly on a circle, counterclockwise
+pub fn circle(origin: &Point, radius: f64) -> Vec<Point> {
+    const SAMPLE_SIZE: usize = 64;
+    let points: [Point; SAMPLE_SIZE] = core::array::from_fn(|idx| {
+        2.0 * (idx as f64) * std::f64::consts::PI / (SAMPLE_SIZE as f64)
+    })
+    .map(|angle| Point {
+        x: origin.x + angle.cos() * radius,
+        y: origin.y + angle.sin() * radius,
+    });
+    Vec::from(points)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_colour_in_html_and_kml() {
+        let colour = voltage_colour(330);
+        assert_eq!(colour.to_html(), "#e41a1c");
+        assert_eq!(colour.to_kml(), "ff1c1ae4");
+        assert_eq!(voltage_colour(11), OTHER_VOLTAGE_COLOUR);
+    }
+
+    #[test]
+    fn escape_markup() {
+        assert_eq!(
+            escape(r#"<a href="x">A & B</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;A &amp; B&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+mod writer;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::data_source::HighVoltageLine;
+use crate::error::{HvError, Result};
+use crate::geometry::Point;
+use crate::query::{DistanceReport, SearchDetails, SearchedSuburb};
+use crate::style::circle;
+
+pub use writer::{
+    CellArray, CellValues, FieldArray, LegacyWriter, PolyData, PolyDataWriter, VtkFormat, XmlWriter,
+};
+
+// Line ids are not always numbers, each cell has the index of its id in the `line_id` field data
+// instead, the same id has the same index.
+#[derive(Default)]
+struct LineIds {
+    ids: Vec<String>,
+}
+
+impl LineIds {
+    fn index_of(&mut self, id: &str) -> i32 {
+        let idx = match self.ids.iter().position(|i| i == id) {
+            Some(idx) => idx,
+            None => {
+                self.ids.push(id.to_owned());
+                self.ids.len() - 1
+            }
+        };
+        idx as i32
+    }
+
+    fn into_field_data(self) -> Vec<FieldArray> {
+        vec![FieldArray {
+            name: "line_id".to_owned(),
+            values: self.ids,
+        }]
+    }
+}
+
+// every suburb with a known boundary as a polygon, with `suburb_id` as cell data
+fn catchments_to_poly_data(suburbs: &[SearchedSuburb]) -> PolyData {
+    let mut data = PolyData::default();
+    let mut suburb_ids = Vec::new();
+    for suburb in suburbs.iter() {
+        if let Some(boundary) = &suburb.boundary {
+            let polygon = data.add_points(boundary.get_vertices());
+            data.polygons.push(polygon);
+            suburb_ids.push(suburb.info.id as i32);
+        }
+    }
+    data.cell_data.push(CellArray {
+        name: "suburb_id".to_owned(),
+        values: CellValues::Int(suburb_ids),
+    });
+    data
+}
+
+// every line with `voltage_kv`, `line_index` and `suburb_id` as cell data, higher voltages first
+fn high_voltages_to_poly_data(high_voltage_lines: &HashMap<u16, Vec<HighVoltageLine>>) -> PolyData {
+    let mut voltages = high_voltage_lines.keys().collect::<Vec<&u16>>();
+    voltages.sort_by_key(|v| std::cmp::Reverse(**v));
+    let mut data = PolyData::default();
+    let mut voltage_kv = Vec::new();
+    let mut line_ids = LineIds::default();
+    let mut line_index = Vec::new();
+    let mut suburb_ids = Vec::new();
+    for voltage in voltages {
+        for line in high_voltage_lines[voltage].iter() {
+            let polyline = data.add_points(line.line.get_vertices());
+            data.lines.push(polyline);
+            voltage_kv.push(*voltage as i32);
+            line_index.push(line_ids.index_of(&line.id));
+            suburb_ids.push(line.suburb_id as i32);
+        }
+    }
+    data.cell_data = vec![
+        CellArray {
+            name: "voltage_kv".to_owned(),
+            values: CellValues::Int(voltage_kv),
+        },
+        CellArray {
+            name: "line_index".to_owned(),
+            values: CellValues::Int(line_index),
+        },
+        CellArray {
+            name: "suburb_id".to_owned(),
+            values: CellValues::Int(suburb_ids),
+        },
+    ];
+    data.field_data = line_ids.into_field_data();
+    data
+}
+
+// The query point as a vertex, and a connector to the nearest point of each voltage from where the
+// distance is measured, which is on the lot if the query is a lot.
+// The query point has a voltage of 0 and a line index of -1.
+fn query_to_poly_data(report: &DistanceReport) -> PolyData {
+    let mut data = PolyData::default();
+    let query = data.add_points(vec![report.location.clone()])[0];
+    data.vertices.push(vec![query]);
+    let mut voltage_kv = vec![0];
+    let mut distance_m = vec![0.0];
+    let mut line_ids = LineIds::default();
+    let mut line_index = vec![-1];
+    for distance in report.distances.iter() {
+        let connector = data.add_points(vec![
+            distance.query_point.clone(),
+            distance.nearest_point.clone(),
+        ]);
+        data.lines.push(connector);
+        voltage_kv.push(distance.voltage as i32);
+        distance_m.push(distance.distance_m);
+        line_index.push(line_ids.index_of(&distance.line_id));
+    }
+    data.cell_data = vec![
+        CellArray {
+            name: "voltage_kv".to_owned(),
+            values: CellValues::Int(voltage_kv),
+        },
+        CellArray {
+            name: "distance_m".to_owned(),
+            values: CellValues::Double(distance_m),
+        },
+        CellArray {
+            name: "line_index".to_owned(),
+            values: CellValues::Int(line_index),
+        },
+    ];
+    data.field_data = line_ids.into_field_data();
+    data
+}
+
+fn circle_to_poly_data(origin: &Point, radius: f64) -> PolyData {
+    let mut data = PolyData::default();
+    let polygon = data.add_points(circle(origin, radius));
+    data.polygons.push(polygon);
+    data
+}
+
+fn write_vtk(
+    dir: &Path,
+    file_name: &str,
+    writer: &dyn PolyDataWriter,
+    data: &PolyData,
+) -> Result<()> {
+    let path = dir.join(format!("{}.{}", file_name, writer.extension()));
+    let write = || -> io::Result<()> {
+        let mut file = BufWriter::new(fs::File::create(&path)?);
+        writer.write(&mut file, data)?;
+        file.flush()
+    };
+    write().map_err(|source| HvError::VtkExport { path, source })
+}
+
+// Everything is in the plane of the report's projection, in meter.
+pub fn export_suburb_to_vtk(
+    dir: &Path,
+    report: &DistanceReport,
+    details: &SearchDetails,
+    format: &VtkFormat,
+) -> Result<()> {
+    fs::create_dir_all(dir).map_err(|source| HvError::VtkExport {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let writer = format.writer();
+    let writer = writer.as_ref();
+    write_vtk(
+        dir,
+        "catchment",
+        writer,
+        &catchments_to_poly_data(&details.suburbs),
+    )?;
+    write_vtk(
+        dir,
+        "high_voltage",
+        writer,
+        &high_voltages_to_poly_data(&details.high_voltage_lines),
+    )?;
+    write_vtk(dir, "query", writer, &query_to_poly_data(report))?;
+    write_vtk(
+        dir,
+        "address_100m",
+        writer,
+        &circle_to_poly_data(&report.location, 100.0),
+    )?;
+    write_vtk(
+        dir,
+        "address_200m",
+        writer,
+        &circle_to_poly_data(&report.location, 200.0),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data_source::SuburbInfo;
+    use crate::geometry::{PolyLine, Polygon, Projection};
+    use crate::query::VoltageDistance;
+
+    fn line(id: &str, suburb_id: u16, points: Vec<Point>) -> HighVoltageLine {
+        HighVoltageLine {
+            positions: points
+                .iter()
+                .map(|p| Projection::default().unproject(p))
+                .collect(),
+            line: PolyLine::new(points).unwrap(),
+            id: id.to_owned(),
+            suburb_id,
+        }
+    }
+
+    fn ints(array: &CellArray) -> &Vec<i32> {
+        match &array.values {
+            CellValues::Int(values) => values,
+            CellValues::Double(_) => panic!("'{}' is not int", array.name),
+        }
+    }
+
+    #[test]
+    fn high_voltages_with_line_attributes_as_cell_data() {
+        let lines = HashMap::from([
+            (
+                132,
+                vec![line(
+                    "12",
+                    3,
+                    vec![
+                        Point { x: 0.0, y: 0.0 },
+                        Point { x: 1.0, y: 0.0 },
+                        Point { x: 2.0, y: 1.0 },
+                    ],
+                )],
+            ),
+            (
+                330,
+                vec![line(
+                    "b",
+                    4,
+                    vec![Point { x: 0.0, y: 5.0 }, Point { x: 1.0, y: 5.0 }],
+                )],
+            ),
+        ]);
+        let data = high_voltages_to_poly_data(&lines);
+        assert_eq!(data.points.len(), 5);
+        // 330kV first
+        assert_eq!(data.lines, vec![vec![0, 1], vec![2, 3, 4]]);
+        let names = data
+            .cell_data
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["voltage_kv", "line_index", "suburb_id"]);
+        assert_eq!(ints(&data.cell_data[0]), &vec![330, 132]);
+        assert_eq!(ints(&data.cell_data[1]), &vec![0, 1]);
+        assert_eq!(ints(&data.cell_data[2]), &vec![4, 3]);
+        assert_eq!(data.field_data[0].name, "line_id");
+        assert_eq!(data.field_data[0].values, vec!["b", "12"]);
+    }
+
+    #[test]
+    fn query_with_connectors() {
+        let mut report = DistanceReport {
+            full_address: None,
+            position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            projection: Projection::default(),
+            location: Point { x: 0.0, y: 0.0 },
+            lot: None,
+            distances: vec![
+                VoltageDistance::new_for_test(330, 120.0, "7"),
+                VoltageDistance::new_for_test(66, 12.5, "b"),
+            ],
+            suburb: None,
+            suburbs_searched: vec![],
+            searched_radius_m: 0.0,
+        };
+        // measured from the edge of a lot rather than the location
+        report.distances[0].query_point = Point { x: 0.0, y: 5.0 };
+        let data = query_to_poly_data(&report);
+        assert_eq!(data.points.len(), 5);
+        data.points[1].assert_close_to(&Point { x: 0.0, y: 5.0 }, 1e-10);
+        data.points[2].assert_close_to(&Point { x: 0.0, y: 120.0 }, 1e-10);
+        assert_eq!(data.vertices, vec![vec![0]]);
+        assert_eq!(data.lines, vec![vec![1, 2], vec![3, 4]]);
+        assert_eq!(data.cell_count(), 3);
+        assert_eq!(ints(&data.cell_data[0]), &vec![0, 330, 66]);
+        assert_eq!(
+            data.cell_data[1].values,
+            CellValues::Double(vec![0.0, 120.0, 12.5])
+        );
+        assert_eq!(ints(&data.cell_data[2]), &vec![-1, 0, 1]);
+        assert_eq!(data.field_data[0].values, vec!["7", "b"]);
+    }
+
+    #[test]
+    fn catchments_skip_unknown_boundary() {
+        let suburb = |id: u16, boundary: Option<Polygon>| SearchedSuburb {
+            info: SuburbInfo {
+                name: format!("suburb {}", id),
+                id,
+                postcode: 2000,
+                position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+                location: Point { x: 0.0, y: 0.0 },
+            },
+            boundary,
+        };
+        let triangle = Polygon::new(vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 0.0 },
+            Point { x: 1.0, y: 1.0 },
+        ])
+        .unwrap();
+        let data = catchments_to_poly_data(&[
+            suburb(1, Some(triangle.clone())),
+            suburb(2, None),
+            suburb(3, Some(triangle)),
+        ]);
+        assert_eq!(data.polygons, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+        assert_eq!(ints(&data.cell_data[0]), &vec![1, 3]);
+    }
+
+    #[test]
+    fn export_every_format() {
+        let dir = std::env::temp_dir().join(format!("hv-distance-vtk-{}", std::process::id()));
+        let report = DistanceReport {
+            full_address: None,
+            position: Point { x: 0.0, y: 0.0 }.to_geo_position(),
+            projection: Projection::default(),
+            location: Point { x: 0.0, y: 0.0 },
+            lot: None,
+            distances: vec![VoltageDistance::new_for_test(330, 120.0, "7")],
+            suburb: None,
+            suburbs_searched: vec![],
+            searched_radius_m: 0.0,
+        };
+        let details = SearchDetails {
+            suburbs: vec![],
+            high_voltage_lines: HashMap::new(),
+        };
+        for (format, extension) in [
+            (VtkFormat::Ascii, "vtk"),
+            (VtkFormat::Binary, "vtk"),
+            (VtkFormat::Xml, "vtp"),
+        ] {
+            export_suburb_to_vtk(&dir, &report, &details, &format).unwrap();
+            for name in [
+                "catchment",
+                "high_voltage",
+                "query",
+                "address_100m",
+                "address_200m",
+            ] {
+                assert!(dir.join(format!("{}.{}", name, extension)).is_file());
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,774 @@
+use std::io::{self, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::geometry::Point;
+
+#[derive(Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum VtkFormat {
+    // legacy .vtk in ASCII
+    Ascii,
+    // legacy .vtk in big-endian binary
+    Binary,
+    // XML .vtp with the data appended in base64
+    Xml,
+}
+
+impl VtkFormat {
+    pub fn writer(&self) -> Box<dyn PolyDataWriter> {
+        match self {
+            VtkFormat::Ascii => Box::new(LegacyWriter { binary: false }),
+            VtkFormat::Binary => Box::new(LegacyWriter { binary: true }),
+            VtkFormat::Xml => Box::new(XmlWriter),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CellValues {
+    Int(Vec<i32>),
+    Double(Vec<f64>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CellArray {
+    pub name: String,
+    pub values: CellValues,
+}
+
+// Strings for the whole data set rather than for each cell, e.g. what an index in the cell data
+// stands for.
+#[derive(Debug, PartialEq)]
+pub struct FieldArray {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+// Points are on the plane, z is always 0.
+#[derive(Default, Debug)]
+pub struct PolyData {
+    pub points: Vec<Point>,
+    // every cell is a list of indices into `points`
+    pub vertices: Vec<Vec<usize>>,
+    pub lines: Vec<Vec<usize>>,
+    pub polygons: Vec<Vec<usize>>,
+    // one value for each cell, vertices first, then lines, then polygons.
+    pub cell_data: Vec<CellArray>,
+    pub field_data: Vec<FieldArray>,
+}
+
+impl PolyData {
+    pub fn cell_count(&self) -> usize {
+        self.vertices.len() + self.lines.len() + self.polygons.len()
+    }
+
+    // adds the points and returns their indices
+    pub fn add_points(&mut self, points: Vec<Point>) -> Vec<usize> {
+        let start = self.points.len();
+        self.points.extend(points);
+        (start..self.points.len()).collect()
+    }
+}
+
+pub trait PolyDataWriter {
+    fn extension(&self) -> &'static str;
+    fn write(&self, writer: &mut dyn Write, data: &PolyData) -> io::Result<()>;
+}
+
+pub struct LegacyWriter {
+    pub binary: bool,
+}
+
+impl LegacyWriter {
+    fn write_f64(&self, writer: &mut dyn Write, values: &[f64]) -> io::Result<()> {
+        if self.binary {
+            for value in values {
+                writer.write_all(&value.to_be_bytes())?;
+            }
+            writeln!(writer)
+        } else {
+            for value in values {
+                writeln!(writer, "{}", value)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn write_i32(&self, writer: &mut dyn Write, values: &[i32]) -> io::Result<()> {
+        if self.binary {
+            for value in values {
+                writer.write_all(&value.to_be_bytes())?;
+            }
+            writeln!(writer)
+        } else {
+            for value in values {
+                writeln!(writer, "{}", value)?;
+            }
+            Ok(())
+        }
+    }
+
+    // A string per line in ascii, with blanks and anything not printable as %XX. In binary, each
+    // string is prefixed by its length, with the top 2 bits of the prefix telling its size.
+    fn write_strings(&self, writer: &mut dyn Write, values: &[String]) -> io::Result<()> {
+        if !self.binary {
+            for value in values {
+                for byte in value.bytes() {
+                    if byte <= b' ' || byte > b'~' || byte == b'%' {
+                        write!(writer, "%{:02X}", byte)?;
+                    } else {
+                        writer.write_all(&[byte])?;
+                    }
+                }
+                writeln!(writer)?;
+            }
+            return Ok(());
+        }
+        for value in values {
+            let len = value.len() as u64;
+            if len < 1 << 6 {
+                writer.write_all(&[0b11 << 6 | len as u8])?;
+            } else if len < 1 << 14 {
+                writer.write_all(&(0b10 << 14 | len as u16).to_be_bytes())?;
+            } else if len < 1 << 30 {
+                writer.write_all(&(0b01 << 30 | len as u32).to_be_bytes())?;
+            } else {
+                writer.write_all(&len.to_be_bytes())?;
+            }
+            writer.write_all(value.as_bytes())?;
+        }
+        writeln!(writer)
+    }
+}
+
+impl PolyDataWriter for LegacyWriter {
+    fn extension(&self) -> &'static str {
+        "vtk"
+    }
+
+    fn write(&self, writer: &mut dyn Write, data: &PolyData) -> io::Result<()> {
+        writeln!(writer, "# vtk DataFile Version 3.0")?;
+        writeln!(writer, "hv-distance")?;
+        writeln!(writer, "{}", if self.binary { "BINARY" } else { "ASCII" })?;
+        writeln!(writer, "DATASET POLYDATA")?;
+        if !data.field_data.is_empty() {
+            writeln!(writer, "FIELD FieldData {}", data.field_data.len())?;
+            for array in data.field_data.iter() {
+                writeln!(writer, "{} 1 {} string", array.name, array.values.len())?;
+                self.write_strings(writer, &array.values)?;
+            }
+        }
+        writeln!(writer, "POINTS {} double", data.points.len())?;
+        let coordinates = data
+            .points
+            .iter()
+            .flat_map(|p| [p.x, p.y, 0.0])
+            .collect::<Vec<f64>>();
+        self.write_f64(writer, &coordinates)?;
+        for (keyword, cells) in [
+            ("VERTICES", &data.vertices),
+            ("LINES", &data.lines),
+            ("POLYGONS", &data.polygons),
+        ] {
+            if cells.is_empty() {
+                continue;
+            }
+            // every cell is the number of points followed by the indices
+            let connectivity = cells
+                .iter()
+                .flat_map(|c| std::iter::once(c.len()).chain(c.iter().copied()))
+                .map(|i| i as i32)
+                .collect::<Vec<i32>>();
+            writeln!(writer, "{} {} {}", keyword, cells.len(), connectivity.len())?;
+            self.write_i32(writer, &connectivity)?;
+        }
+        if data.cell_data.is_empty() {
+            return Ok(());
+        }
+        writeln!(writer, "CELL_DATA {}", data.cell_count())?;
+        for array in data.cell_data.iter() {
+            match &array.values {
+                CellValues::Int(values) => {
+                    writeln!(writer, "SCALARS {} int 1", array.name)?;
+                    writeln!(writer, "LOOKUP_TABLE default")?;
+                    self.write_i32(writer, values)?;
+                }
+                CellValues::Double(values) => {
+                    writeln!(writer, "SCALARS {} double 1", array.name)?;
+                    writeln!(writer, "LOOKUP_TABLE default")?;
+                    self.write_f64(writer, values)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Every array is appended after the xml as its own base64 block, the byte count of the array
+// as UInt64 followed by the array in little-endian.
+pub struct XmlWriter;
+
+struct AppendedData {
+    encoded: String,
+}
+
+impl AppendedData {
+    fn data_array(
+        &mut self,
+        r#type: &str,
+        name: &str,
+        components: usize,
+        bytes: Vec<u8>,
+    ) -> String {
+        let offset = self.encoded.len();
+        let mut block = (bytes.len() as u64).to_le_bytes().to_vec();
+        block.extend(bytes);
+        self.encoded.push_str(&STANDARD.encode(block));
+        format!(
+            r#"<DataArray type="{}" Name="{}" NumberOfComponents="{}" format="appended" offset="{}"/>"#,
+            r#type, name, components, offset
+        )
+    }
+}
+
+impl AppendedData {
+    // every string ends with a null
+    fn string_array(&mut self, name: &str, values: &[String]) -> String {
+        let bytes = values
+            .iter()
+            .flat_map(|v| v.bytes().chain(std::iter::once(0)))
+            .collect();
+        let tag = self.data_array("String", name, 1, bytes);
+        tag.replacen(
+            " format=",
+            &format!(r#" NumberOfTuples="{}" format="#, values.len()),
+            1,
+        )
+    }
+}
+
+impl PolyDataWriter for XmlWriter {
+    fn extension(&self) -> &'static str {
+        "vtp"
+    }
+
+    fn write(&self, writer: &mut dyn Write, data: &PolyData) -> io::Result<()> {
+        let mut appended = AppendedData {
+            encoded: String::new(),
+        };
+        let points = appended.data_array(
+            "Float64",
+            "Points",
+            3,
+            data.points
+                .iter()
+                .flat_map(|p| [p.x, p.y, 0.0])
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+        );
+        let mut cells = Vec::<(&str, Vec<String>)>::new();
+        for (tag, cell_list) in [
+            ("Verts", &data.vertices),
+            ("Lines", &data.lines),
+            ("Polys", &data.polygons),
+        ] {
+            let connectivity = cell_list
+                .iter()
+                .flatten()
+                .flat_map(|i| (*i as i64).to_le_bytes())
+                .collect();
+            let offsets = cell_list
+                .iter()
+                .scan(0_i64, |end, c| {
+                    *end += c.len() as i64;
+                    Some(*end)
+                })
+                .flat_map(|i| i.to_le_bytes())
+                .collect();
+            cells.push((
+                tag,
+                vec![
+                    appended.data_array("Int64", "connectivity", 1, connectivity),
+                    appended.data_array("Int64", "offsets", 1, offsets),
+                ],
+            ));
+        }
+        let cell_data = data
+            .cell_data
+            .iter()
+            .map(|array| match &array.values {
+                CellValues::Int(values) => appended.data_array(
+                    "Int32",
+                    &array.name,
+                    1,
+                    values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+                ),
+                CellValues::Double(values) => appended.data_array(
+                    "Float64",
+                    &array.name,
+                    1,
+                    values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+                ),
+            })
+            .collect::<Vec<String>>();
+        let field_data = data
+            .field_data
+            .iter()
+            .map(|array| appended.string_array(&array.name, &array.values))
+            .collect::<Vec<String>>();
+
+        writeln!(writer, r#"<?xml version="1.0"?>"#)?;
+        writeln!(
+            writer,
+            r#"<VTKFile type="PolyData" version="1.0" byte_order="LittleEndian" header_type="UInt64">"#
+        )?;
+        writeln!(writer, "  <PolyData>")?;
+        if !field_data.is_empty() {
+            writeln!(writer, "    <FieldData>")?;
+            for array in field_data {
+                writeln!(writer, "      {}", array)?;
+            }
+            writeln!(writer, "    </FieldData>")?;
+        }
+        writeln!(
+            writer,
+            r#"    <Piece NumberOfPoints="{}" NumberOfVerts="{}" NumberOfLines="{}" NumberOfStrips="0" NumberOfPolys="{}">"#,
+            data.points.len(),
+            data.vertices.len(),
+            data.lines.len(),
+            data.polygons.len()
+        )?;
+        writeln!(
+            writer,
+            "      <Points>\n        {}\n      </Points>",
+            points
+        )?;
+        for (tag, arrays) in cells {
+            writeln!(writer, "      <{}>", tag)?;
+            for array in arrays {
+                writeln!(writer, "        {}", array)?;
+            }
+            writeln!(writer, "      </{}>", tag)?;
+        }
+        writeln!(writer, "      <CellData>")?;
+        for array in cell_data {
+            writeln!(writer, "        {}", array)?;
+        }
+        writeln!(writer, "      </CellData>")?;
+        writeln!(writer, "    </Piece>")?;
+        writeln!(writer, "  </PolyData>")?;
+        writeln!(writer, r#"  <AppendedData encoding="base64">"#)?;
+        writeln!(writer, "   _{}", appended.encoded)?;
+        writeln!(writer, "  </AppendedData>")?;
+        writeln!(writer, "</VTKFile>")
+    }
+}
+
+// Just enough of a reader to read back what is written above.
+#[cfg(test)]
+mod reader {
+    use super::*;
+
+    fn to_cells(connectivity: &[i64], offsets: &[i64]) -> Vec<Vec<usize>> {
+        let mut start = 0;
+        offsets
+            .iter()
+            .map(|end| {
+                let cell = connectivity[start as usize..*end as usize]
+                    .iter()
+                    .map(|i| *i as usize)
+                    .collect();
+                start = *end;
+                cell
+            })
+            .collect()
+    }
+
+    fn to_points(coordinates: &[f64]) -> Vec<Point> {
+        coordinates
+            .chunks(3)
+            .map(|c| Point { x: c[0], y: c[1] })
+            .collect()
+    }
+
+    struct LegacyReader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+        binary: bool,
+    }
+
+    impl<'a> LegacyReader<'a> {
+        fn line(&mut self) -> Option<&'a str> {
+            if self.pos >= self.bytes.len() {
+                return None;
+            }
+            let end = self.bytes[self.pos..]
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(self.bytes.len(), |i| self.pos + i);
+            let line = std::str::from_utf8(&self.bytes[self.pos..end]).unwrap();
+            self.pos = end + 1;
+            Some(line)
+        }
+
+        fn values<T: std::str::FromStr>(
+            &mut self,
+            count: usize,
+            size: usize,
+            from_be: fn(&[u8]) -> T,
+        ) -> Vec<T>
+        where
+            T::Err: std::fmt::Debug,
+        {
+            if self.binary {
+                let values = self.bytes[self.pos..self.pos + count * size]
+                    .chunks(size)
+                    .map(from_be)
+                    .collect();
+                // the newline after the binary data
+                self.pos += count * size + 1;
+                values
+            } else {
+                let mut values = Vec::new();
+                while values.len() < count {
+                    values.extend(
+                        self.line()
+                            .unwrap()
+                            .split_whitespace()
+                            .map(|v| v.parse().unwrap()),
+                    );
+                }
+                values
+            }
+        }
+    }
+
+    impl LegacyReader<'_> {
+        fn strings(&mut self, count: usize) -> Vec<String> {
+            if !self.binary {
+                return (0..count)
+                    .map(|_| {
+                        let line = self.line().unwrap();
+                        let mut bytes = Vec::new();
+                        let mut idx = 0;
+                        while idx < line.len() {
+                            if &line[idx..idx + 1] == "%" {
+                                bytes
+                                    .push(u8::from_str_radix(&line[idx + 1..idx + 3], 16).unwrap());
+                                idx += 3;
+                            } else {
+                                bytes.push(line.as_bytes()[idx]);
+                                idx += 1;
+                            }
+                        }
+                        String::from_utf8(bytes).unwrap()
+                    })
+                    .collect();
+            }
+            let values = (0..count)
+                .map(|_| {
+                    let first = self.bytes[self.pos];
+                    let (prefix, len) = match first >> 6 {
+                        0b11 => (1, (first & 0x3f) as usize),
+                        0b10 => (
+                            2,
+                            (u16::from_be_bytes(
+                                self.bytes[self.pos..self.pos + 2].try_into().unwrap(),
+                            ) & 0x3fff) as usize,
+                        ),
+                        _ => panic!("long strings are not read back"),
+                    };
+                    let start = self.pos + prefix;
+                    self.pos = start + len;
+                    String::from_utf8(self.bytes[start..start + len].to_vec()).unwrap()
+                })
+                .collect();
+            // the newline after the binary data
+            self.pos += 1;
+            values
+        }
+    }
+
+    fn f64_from_be(b: &[u8]) -> f64 {
+        f64::from_be_bytes(b.try_into().unwrap())
+    }
+
+    fn i32_from_be(b: &[u8]) -> i32 {
+        i32::from_be_bytes(b.try_into().unwrap())
+    }
+
+    pub fn read_legacy(bytes: &[u8]) -> PolyData {
+        let mut reader = LegacyReader {
+            bytes,
+            pos: 0,
+            binary: false,
+        };
+        assert_eq!(reader.line(), Some("# vtk DataFile Version 3.0"));
+        reader.line();
+        reader.binary = reader.line() == Some("BINARY");
+        assert_eq!(reader.line(), Some("DATASET POLYDATA"));
+        let mut data = PolyData::default();
+        let mut scalar: Option<(String, String)> = None;
+        while let Some(line) = reader.line() {
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            match words.as_slice() {
+                ["POINTS", count, "double"] => {
+                    let count = count.parse::<usize>().unwrap();
+                    data.points = to_points(&reader.values(count * 3, 8, f64_from_be));
+                }
+                [keyword @ ("VERTICES" | "LINES" | "POLYGONS"), _, size] => {
+                    let connectivity = reader.values(size.parse().unwrap(), 4, i32_from_be);
+                    let mut cells = Vec::new();
+                    let mut idx = 0;
+                    while idx < connectivity.len() {
+                        let len = connectivity[idx] as usize;
+                        cells.push(
+                            connectivity[idx + 1..idx + 1 + len]
+                                .iter()
+                                .map(|i| *i as usize)
+                                .collect(),
+                        );
+                        idx += len + 1;
+                    }
+                    match *keyword {
+                        "VERTICES" => data.vertices = cells,
+                        "LINES" => data.lines = cells,
+                        _ => data.polygons = cells,
+                    }
+                }
+                ["CELL_DATA", _] | ["FIELD", "FieldData", _] => {}
+                [name, "1", count, "string"] => {
+                    let values = reader.strings(count.parse().unwrap());
+                    data.field_data.push(FieldArray {
+                        name: name.to_string(),
+                        values,
+                    });
+                }
+                ["SCALARS", name, r#type, "1"] => {
+                    scalar = Some((name.to_string(), r#type.to_string()));
+                }
+                ["LOOKUP_TABLE", "default"] => {
+                    let (name, r#type) = scalar.take().unwrap();
+                    let count = data.cell_count();
+                    let values = match r#type.as_str() {
+                        "int" => CellValues::Int(reader.values(count, 4, i32_from_be)),
+                        _ => CellValues::Double(reader.values(count, 8, f64_from_be)),
+                    };
+                    data.cell_data.push(CellArray { name, values });
+                }
+                _ => panic!("unexpected line '{}'", line),
+            }
+        }
+        data
+    }
+
+    fn attribute<'a>(tag: &'a str, name: &str) -> &'a str {
+        let start = tag.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+        let end = start + tag[start..].find('"').unwrap();
+        &tag[start..end]
+    }
+
+    pub fn read_xml(xml: &str) -> PolyData {
+        let appended = xml
+            .split("<AppendedData encoding=\"base64\">")
+            .nth(1)
+            .unwrap()
+            .split("</AppendedData>")
+            .next()
+            .unwrap()
+            .trim()
+            .strip_prefix('_')
+            .unwrap();
+        // every block decodes to its byte count as UInt64 followed by the bytes
+        let block = |offset: usize| -> Vec<u8> {
+            let header = STANDARD.decode(&appended[offset..offset + 12]).unwrap();
+            let size = u64::from_le_bytes(header[..8].try_into().unwrap()) as usize;
+            let encoded_len = (8 + size).div_ceil(3) * 4;
+            let decoded = STANDARD
+                .decode(&appended[offset..offset + encoded_len])
+                .unwrap();
+            decoded[8..].to_vec()
+        };
+        let mut data = PolyData::default();
+        let mut section = "";
+        let mut connectivity = Vec::<i64>::new();
+        for tag in xml.split('<').map(|t| t.split('>').next().unwrap()) {
+            match tag.split_whitespace().next().unwrap_or("") {
+                name @ ("Points" | "Verts" | "Lines" | "Polys" | "CellData" | "FieldData") => {
+                    section = name
+                }
+                "DataArray" => {
+                    let bytes = block(attribute(tag, "offset").parse().unwrap());
+                    let name = attribute(tag, "Name");
+                    let as_f64 = || {
+                        bytes
+                            .chunks(8)
+                            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+                            .collect::<Vec<f64>>()
+                    };
+                    let as_i64 = || {
+                        bytes
+                            .chunks(8)
+                            .map(|b| i64::from_le_bytes(b.try_into().unwrap()))
+                            .collect::<Vec<i64>>()
+                    };
+                    match (section, name) {
+                        ("Points", _) => data.points = to_points(&as_f64()),
+                        (_, "connectivity") => connectivity = as_i64(),
+                        (cells, "offsets") => {
+                            let cells_read = to_cells(&connectivity, &as_i64());
+                            match cells {
+                                "Verts" => data.vertices = cells_read,
+                                "Lines" => data.lines = cells_read,
+                                _ => data.polygons = cells_read,
+                            }
+                        }
+                        ("FieldData", name) => {
+                            // every string ends with a null
+                            let values = bytes
+                                .split(|b| *b == 0)
+                                .take(attribute(tag, "NumberOfTuples").parse().unwrap())
+                                .map(|v| String::from_utf8(v.to_vec()).unwrap())
+                                .collect();
+                            data.field_data.push(FieldArray {
+                                name: name.to_owned(),
+                                values,
+                            });
+                        }
+                        ("CellData", name) => {
+                            let values = match attribute(tag, "type") {
+                                "Int32" => CellValues::Int(
+                                    bytes
+                                        .chunks(4)
+                                        .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+                                        .collect(),
+                                ),
+                                _ => CellValues::Double(as_f64()),
+                            };
+                            data.cell_data.push(CellArray {
+                                name: name.to_owned(),
+                                values,
+                            });
+                        }
+                        _ => panic!("unexpected data array '{}'", tag),
+                    }
+                }
+                _ => {}
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn poly_data() -> PolyData {
+        let mut data = PolyData::default();
+        let idx = data.add_points(vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.5, y: -2.25 },
+            Point {
+                x: 12345.678,
+                y: 0.1,
+            },
+            Point { x: -3.0, y: 7.0 },
+        ]);
+        data.vertices = vec![vec![idx[0]]];
+        data.lines = vec![vec![idx[0], idx[1]], vec![idx[1], idx[2], idx[3]]];
+        data.polygons = vec![vec![idx[0], idx[1], idx[3]]];
+        data.cell_data = vec![
+            CellArray {
+                name: "voltage_kv".to_owned(),
+                values: CellValues::Int(vec![0, 330, 132, 0]),
+            },
+            CellArray {
+                name: "distance_m".to_owned(),
+                values: CellValues::Double(vec![0.0, 1.25, 1e-3, 98765.4321]),
+            },
+        ];
+        data.field_data = vec![FieldArray {
+            name: "line_id".to_owned(),
+            values: vec![
+                "512".to_owned(),
+                "feeder 7/b".to_owned(),
+                "100%".to_owned(),
+                "x".repeat(100),
+            ],
+        }];
+        data
+    }
+
+    fn write(format: VtkFormat, data: &PolyData) -> Vec<u8> {
+        let mut buf = Vec::<u8>::new();
+        format.writer().write(&mut buf, data).unwrap();
+        buf
+    }
+
+    fn assert_same(left: &PolyData, right: &PolyData) {
+        assert_eq!(left.points.len(), right.points.len());
+        for (l, r) in left.points.iter().zip(right.points.iter()) {
+            assert_eq!((l.x, l.y), (r.x, r.y));
+        }
+        assert_eq!(left.vertices, right.vertices);
+        assert_eq!(left.lines, right.lines);
+        assert_eq!(left.polygons, right.polygons);
+        assert_eq!(left.cell_data, right.cell_data);
+        assert_eq!(left.field_data, right.field_data);
+    }
+
+    #[test]
+    fn legacy_ascii_round_trip() {
+        let data = poly_data();
+        let buf = write(VtkFormat::Ascii, &data);
+        assert!(String::from_utf8(buf.clone())
+            .unwrap()
+            .contains("\nASCII\n"));
+        assert_same(&reader::read_legacy(&buf), &data);
+    }
+
+    #[test]
+    fn legacy_binary_round_trip() {
+        let data = poly_data();
+        let buf = write(VtkFormat::Binary, &data);
+        assert_same(&reader::read_legacy(&buf), &data);
+    }
+
+    #[test]
+    fn legacy_binary_is_big_endian() {
+        let mut data = PolyData::default();
+        data.add_points(vec![Point { x: 1.0, y: 2.0 }]);
+        let buf = write(VtkFormat::Binary, &data);
+        let header =
+            "# vtk DataFile Version 3.0\nhv-distance\nBINARY\nDATASET POLYDATA\nPOINTS 1 double\n";
+        assert!(buf.starts_with(header.as_bytes()));
+        assert_eq!(buf[header.len()..header.len() + 8], 1.0_f64.to_be_bytes());
+    }
+
+    #[test]
+    fn xml_round_trip() {
+        let data = poly_data();
+        let buf = write(VtkFormat::Xml, &data);
+        let xml = String::from_utf8(buf).unwrap();
+        assert!(xml.contains(r#"NumberOfPoints="4" NumberOfVerts="1" NumberOfLines="2""#));
+        assert_same(&reader::read_xml(&xml), &data);
+    }
+
+    #[test]
+    fn empty_round_trip() {
+        let data = PolyData::default();
+        for format in [VtkFormat::Ascii, VtkFormat::Binary] {
+            assert_same(&reader::read_legacy(&write(format, &data)), &data);
+        }
+        let xml = String::from_utf8(write(VtkFormat::Xml, &data)).unwrap();
+        assert_same(&reader::read_xml(&xml), &data);
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(VtkFormat::Ascii.writer().extension(), "vtk");
+        assert_eq!(VtkFormat::Binary.writer().extension(), "vtk");
+        assert_eq!(VtkFormat::Xml.writer().extension(), "vtp");
+    }
+}